@@ -0,0 +1,52 @@
+//! Times unescaping and counts the allocations made, for a few kinds of input.
+//!
+//! Run with `cargo bench`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use unescape_zero_copy::unescape_default;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn bench(name: &str, input: &str) {
+    const ITERATIONS: u32 = 1000;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(unescape_default(black_box(input)).unwrap());
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS as usize;
+    println!("{name:>16}: {elapsed:>10.2?}/iter, {allocations} allocations/iter");
+}
+
+fn main() {
+    bench("no escapes", &"plain text without escapes ".repeat(1000));
+    bench(
+        "few escapes",
+        &format!("{}\\n", "a long run of text ".repeat(1000)),
+    );
+    bench("many escapes", &r"\n\t\x41é\u{1F600}\\".repeat(1000));
+    bench("hex escapes", &r"\x41\u00e9\U0001F600".repeat(1000));
+}
@@ -0,0 +1,1241 @@
+//! Escaping strings into the format understood by the default unescaper.
+
+use core::fmt::Write;
+
+use crate::{default_escape_sequence, Cow, Dialect, Error, Options};
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+/// Whether the character must be escaped by [`escape`].
+#[inline]
+pub(crate) fn needs_escape(c: char) -> bool {
+    matches!(c, '\\' | '"' | '\'') || c.is_control()
+}
+
+/// The narrowest width for [`escape_wrapped`], fitting the longest escape
+/// sequence it writes (e.g. `\x01`) and a line continuation.
+const MIN_WRAP_WIDTH: usize = 5;
+
+/// Options for how the escaping functions write their escape sequences.
+///
+/// The escaping functions of this crate use the default style; the methods
+/// here are the same functions with the style applied.
+///
+/// ```
+/// # use unescape_zero_copy::EscapeStyle;
+/// let upper = EscapeStyle {
+///     uppercase_hex: true,
+///     ..EscapeStyle::new()
+/// };
+/// assert_eq!(upper.escape("\x7f\u{85}"), r"\x7F\x85");
+/// assert_eq!(upper.escape_char('\u{1F600}'), r"\u{1F600}");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EscapeStyle {
+    /// Whether hex digits are written in uppercase (`\x7F`) rather than
+    /// lowercase (`\x7f`); defaults to lowercase, as the escaping functions
+    /// have always written them. The unescaper accepts either case.
+    pub uppercase_hex: bool,
+}
+
+impl EscapeStyle {
+    /// Creates the default style, usable in `const` contexts.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            uppercase_hex: false,
+        }
+    }
+
+    /// Pushes the number in hex, padded with zeros to at least `width` digits.
+    fn push_hex(&self, out: &mut String, num: u32, width: usize) {
+        let _ = if self.uppercase_hex {
+            write!(out, "{num:0width$X}")
+        } else {
+            write!(out, "{num:0width$x}")
+        };
+    }
+
+    /// Pushes the escape sequence for the character onto the string.
+    pub(crate) fn push_escaped(&self, out: &mut String, c: char) {
+        match c {
+            '\x07' => out.push_str("\\a"),
+            '\x08' => out.push_str("\\b"),
+            '\x0C' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x0B' => out.push_str("\\v"),
+            '\0' => out.push_str("\\0"),
+            '\\' | '"' | '\'' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\x01'..='\u{FF}' => {
+                out.push_str("\\x");
+                self.push_hex(out, c as u32, 2);
+            }
+            _ => {
+                out.push_str("\\u{");
+                self.push_hex(out, c as u32, 0);
+                out.push('}');
+            }
+        }
+    }
+
+    /// Pushes the shortest escape sequence for the character onto the string.
+    fn push_shortest(&self, out: &mut String, c: char) {
+        if let '\u{100}'..='\u{FFFF}' = c {
+            out.push_str("\\u");
+            self.push_hex(out, c as u32, 4);
+        } else {
+            self.push_escaped(out, c);
+        }
+    }
+
+    /// Returns the shortest escape sequence for the character, as
+    /// [`escape_char_shortest`] does.
+    pub fn escape_char_shortest(&self, c: char) -> String {
+        let mut out = String::new();
+        self.push_shortest(&mut out, c);
+        out
+    }
+
+    /// Escapes the string into ASCII with the shortest escape sequences, as
+    /// [`escape_canonical`] does.
+    pub fn escape_canonical<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        escape_by(
+            s,
+            |c| needs_escape(c) || !c.is_ascii(),
+            |out, c| self.push_shortest(out, c),
+        )
+    }
+
+    /// Returns the escape sequence for the character, as [`escape_char`]
+    /// does.
+    pub fn escape_char(&self, c: char) -> String {
+        let mut out = String::new();
+        self.push_escaped(&mut out, c);
+        out
+    }
+
+    /// Escapes the string, as [`escape`] does.
+    #[inline]
+    pub fn escape<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        self.escape_with(s, needs_escape)
+    }
+
+    /// Escapes the characters of the string for which the function returns
+    /// `true`, as [`escape_with`] does.
+    #[inline]
+    pub fn escape_with<'a, F>(&self, s: &'a str, should_escape: F) -> Cow<'a, str>
+    where
+        F: FnMut(char) -> bool,
+    {
+        escape_by(s, should_escape, |out, c| self.push_escaped(out, c))
+    }
+
+    /// Wraps the string in quotes, escaping it as [`quote_with`] does.
+    pub fn quote_with(&self, s: &str, quote: char) -> String {
+        let escaped = self.escape_with(s, |c| c == quote || c.is_control());
+        let mut out = String::with_capacity(escaped.len() + 2 * quote.len_utf8());
+        out.push(quote);
+        out.push_str(&escaped);
+        out.push(quote);
+        out
+    }
+
+    /// Escapes the string, splitting it into lines of at most `width`
+    /// characters, as [`escape_wrapped`] does.
+    pub fn escape_wrapped(&self, s: &str, width: usize) -> String {
+        let width = width.max(MIN_WRAP_WIDTH);
+        let mut out = String::with_capacity(s.len());
+        let mut unit = String::new();
+        let mut line_len = 0;
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            unit.clear();
+            if c == '\0' && chars.peek().is_some_and(char::is_ascii_digit) {
+                unit.push_str("\\x00");
+            } else if needs_escape(c) {
+                self.push_escaped(&mut unit, c);
+            } else {
+                unit.push(c);
+            }
+            let unit_len = unit.chars().count();
+            // leave room for the backslash of a line continuation
+            if line_len > 0 && line_len + unit_len >= width {
+                out.push_str("\\\n");
+                line_len = 0;
+            }
+            if line_len == 0 && c == ' ' {
+                // leading spaces are trimmed along with a line continuation
+                unit.clear();
+                self.push_escaped(&mut unit, c);
+            }
+            out.push_str(&unit);
+            line_len += unit.chars().count();
+        }
+        out
+    }
+
+    /// Escapes the string for the dialect, as [`escape_dialect`] does.
+    pub fn escape_dialect<'a>(&self, s: &'a str, dialect: Dialect) -> Cow<'a, str> {
+        match dialect {
+            Dialect::C => self.escape(s),
+            Dialect::Toml | Dialect::Json => escape_by(
+                s,
+                |c| c == '"' || c.is_control(),
+                |out, c| match c {
+                    '\x08' => out.push_str("\\b"),
+                    '\x0C' => out.push_str("\\f"),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    '\\' | '"' => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    // control characters are all in the Basic Multilingual Plane
+                    _ => {
+                        out.push_str("\\u");
+                        self.push_hex(out, c as u32, 4);
+                    }
+                },
+            ),
+            Dialect::Rust => escape_by(s, needs_escape, |out, c| match c {
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                '\0' => out.push_str("\\0"),
+                '\\' | '"' | '\'' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                '\0'..='\x7F' => {
+                    out.push_str("\\x");
+                    self.push_hex(out, c as u32, 2);
+                }
+                _ => {
+                    out.push_str("\\u{");
+                    self.push_hex(out, c as u32, 0);
+                    out.push('}');
+                }
+            }),
+            Dialect::Minimal => escape_by(
+                s,
+                |c| matches!(c, '"' | '\''),
+                |out, c| {
+                    out.push('\\');
+                    out.push(c);
+                },
+            ),
+            Dialect::Snailquote => escape_by(
+                s,
+                |c| matches!(c, '"' | '$' | '`') || c.is_control(),
+                |out, c| match c {
+                    '\x1B' => out.push_str("\\e"),
+                    '\x07'..='\x0D' | '\\' | '"' => self.push_escaped(out, c),
+                    '$' | '`' => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    _ => {
+                        out.push_str("\\u{");
+                        self.push_hex(out, c as u32, 0);
+                        out.push('}');
+                    }
+                },
+            ),
+            // `\u` escapes are avoided, as Java translates them before parsing
+            // string literals; control characters are all below `\377`
+            Dialect::Java => escape_by(
+                s,
+                |c| c == '"' || c.is_control(),
+                |out, c| match c {
+                    '\x08' => out.push_str("\\b"),
+                    '\t' => out.push_str("\\t"),
+                    '\n' => out.push_str("\\n"),
+                    '\x0C' => out.push_str("\\f"),
+                    '\r' => out.push_str("\\r"),
+                    '\\' | '"' => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    _ => {
+                        let num = c as u32;
+                        out.push('\\');
+                        for shift in [6, 3, 0] {
+                            out.push(char::from(b'0' + (num >> shift & 7) as u8));
+                        }
+                    }
+                },
+            ),
+            Dialect::Postgres => escape_by(
+                s,
+                |c| c == '\'' || c.is_control(),
+                |out, c| match c {
+                    '\x08' => out.push_str("\\b"),
+                    '\x0C' => out.push_str("\\f"),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    '\\' => out.push_str("\\\\"),
+                    '\'' => out.push_str("''"),
+                    '\0'..='\x7F' => {
+                        out.push_str("\\x");
+                        self.push_hex(out, c as u32, 2);
+                    }
+                    _ => {
+                        out.push_str("\\u");
+                        self.push_hex(out, c as u32, 4);
+                    }
+                },
+            ),
+        }
+    }
+
+    /// Converts the string from one dialect's escape sequences to another's,
+    /// as [`reescape`] does.
+    pub fn reescape(&self, s: &str, from: Dialect, to: Dialect) -> Result<String, Error> {
+        let options = Options {
+            dialect: from,
+            ..Options::new()
+        };
+        let unescaped = options.unescape(s)?;
+        Ok(self.escape_dialect(&unescaped, to).into_owned())
+    }
+
+    /// Escapes the string with every hex escape in braces, as
+    /// [`escape_braced`] does.
+    pub fn escape_braced<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        escape_by(s, needs_escape, |out, c| match c {
+            '\x07'..='\x0D' | '\\' | '"' | '\'' => self.push_escaped(out, c),
+            _ => {
+                out.push_str("\\x{");
+                self.push_hex(out, c as u32, 0);
+                out.push('}');
+            }
+        })
+    }
+
+    /// Escapes the string using only `\u{...}` escapes, as
+    /// [`escape_unicode_all`] does.
+    pub fn escape_unicode_all<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        escape_by(
+            s,
+            |c| needs_escape(c) || !c.is_ascii(),
+            |out, c| match c {
+                '\\' | '"' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                _ => {
+                    out.push_str("\\u{");
+                    self.push_hex(out, c as u32, 0);
+                    out.push('}');
+                }
+            },
+        )
+    }
+
+    /// Escapes the string into ASCII for JSON, as [`escape_json_ascii`] does.
+    pub fn escape_json_ascii<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        escape_by(
+            s,
+            |c| c == '"' || c.is_control() || !c.is_ascii(),
+            |out, c| match c {
+                '\x08' => out.push_str("\\b"),
+                '\x0C' => out.push_str("\\f"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                '\\' | '"' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                _ => {
+                    for unit in c.encode_utf16(&mut [0; 2]) {
+                        out.push_str("\\u");
+                        self.push_hex(out, u32::from(*unit), 4);
+                    }
+                }
+            },
+        )
+    }
+
+    /// Escapes UTF-16 code units, as [`escape_utf16`] does.
+    pub fn escape_utf16(&self, units: &[u16]) -> String {
+        let mut out = String::with_capacity(units.len());
+        // the offset in code units, as characters may take two
+        let mut idx = 0;
+        for ch in char::decode_utf16(units.iter().copied()) {
+            idx += ch.as_ref().map_or(1, |c| c.len_utf16());
+            match ch {
+                Ok('\0') => {
+                    let digit = units
+                        .get(idx)
+                        .is_some_and(|&unit| (u16::from(b'0')..=u16::from(b'9')).contains(&unit));
+                    out.push_str(if digit { "\\x00" } else { "\\0" });
+                }
+                Ok(c) if needs_escape(c) => self.push_escaped(&mut out, c),
+                Ok(c) => out.push(c),
+                Err(err) => {
+                    out.push_str("\\u{");
+                    self.push_hex(&mut out, err.unpaired_surrogate().into(), 0);
+                    out.push('}');
+                }
+            }
+        }
+        out
+    }
+
+    /// Escapes a byte slice into an ASCII-only string, as [`escape_bytes`]
+    /// does.
+    pub fn escape_bytes(&self, b: &[u8]) -> String {
+        let mut out = String::with_capacity(b.len());
+        for &byte in b {
+            if (0x20..=0x7E).contains(&byte) && !matches!(byte, b'\\' | b'"' | b'\'') {
+                out.push(char::from(byte));
+            } else {
+                out.push_str("\\x");
+                self.push_hex(&mut out, byte.into(), 2);
+            }
+        }
+        out
+    }
+}
+
+/// Returns the escape sequence for the character, whether or not it needs to
+/// be escaped.
+///
+/// Named escapes (e.g. `\n`) are used where available, then `\xNN` for
+/// characters up to U+00FF, and `\u{...}` for everything else. NUL is
+/// escaped as `\0`; when escaping strings, `\x00` is used instead before a
+/// digit, which would otherwise be read as part of an octal escape.
+#[inline]
+pub fn escape_char(c: char) -> String {
+    EscapeStyle::new().escape_char(c)
+}
+
+/// Returns the shortest escape sequence for the character.
+///
+/// Named escapes (e.g. `\n`) are used where available, then `\xNN` for
+/// characters up to U+00FF, `\uNNNN` for the rest of the Basic Multilingual
+/// Plane, and `\u{...}` for everything else. Octal escapes are never used,
+/// since a digit after them would change their meaning.
+///
+/// ```
+/// # use unescape_zero_copy::escape_char_shortest;
+/// assert_eq!(escape_char_shortest('\u{20AC}'), r"\u20ac");
+/// assert_eq!(escape_char_shortest('\u{1F600}'), r"\u{1f600}");
+/// ```
+#[inline]
+pub fn escape_char_shortest(c: char) -> String {
+    EscapeStyle::new().escape_char_shortest(c)
+}
+
+/// Escapes the string into ASCII, using the shortest escape sequence for each
+/// escaped character, as given by [`escape_char_shortest`].
+///
+/// Non-ASCII characters are escaped along with every character [`escape`]
+/// would escape, giving a canonical form which the default unescaper
+/// reproduces. Strings without any characters to escape are returned
+/// borrowed.
+///
+/// ```
+/// # use unescape_zero_copy::escape_canonical;
+/// assert_eq!(escape_canonical("caf\u{e9} \u{20AC}5\n"), r"caf\xe9 \u20ac5\n");
+/// ```
+#[inline]
+pub fn escape_canonical(s: &str) -> Cow<'_, str> {
+    EscapeStyle::new().escape_canonical(s)
+}
+
+/// Escapes the string so that the default unescaper reproduces it.
+///
+/// Backslashes, quotes, and control characters are escaped; all other
+/// characters, including non-ASCII ones, are kept as-is. Strings without any
+/// characters to escape are returned borrowed.
+#[inline]
+pub fn escape(s: &str) -> Cow<'_, str> {
+    EscapeStyle::new().escape(s)
+}
+
+/// Wraps the string in double quotes as a string literal, which the default
+/// unescaper reproduces once the quotes are stripped.
+///
+/// This is [`quote_with`] for `"`.
+///
+/// ```
+/// # use unescape_zero_copy::quote;
+/// assert_eq!(quote(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+/// ```
+#[inline]
+pub fn quote(s: &str) -> String {
+    quote_with(s, '"')
+}
+
+/// Wraps the string in quotes of the given character as a string literal,
+/// which the default unescaper reproduces once the quotes are stripped.
+///
+/// Backslashes, the quote character, and control characters are escaped, so
+/// the literal ends at the closing quote and fits on one line; any other
+/// characters (including other quotes) are kept as-is.
+///
+/// ```
+/// # use unescape_zero_copy::quote_with;
+/// assert_eq!(quote_with("it's \"this\"", '\''), r#"'it\'s "this"'"#);
+/// assert_eq!(quote_with("a`b", '`'), r"`a\x60b`");
+/// ```
+#[inline]
+pub fn quote_with(s: &str, quote: char) -> String {
+    EscapeStyle::new().quote_with(s, quote)
+}
+
+/// Escapes the string as [`escape`] does, then splits it into lines of at
+/// most `width` characters with line continuations, for unescaping with
+/// [`Options::trim_line_continuation`] enabled.
+///
+/// A line continuation is a backslash at the end of a line, which the default
+/// unescaper keeps as a line break, so only unescaping with
+/// [`Options::trim_line_continuation`] reproduces the string. Escape sequences
+/// are never split across lines, and a space at the start of a line is
+/// escaped, since it would otherwise be trimmed along with the line
+/// continuation. Widths below 5, too narrow for an escape sequence such as
+/// `\x01` and a line continuation, are treated as 5.
+///
+/// ```
+/// # use unescape_zero_copy::{escape_wrapped, Options};
+/// let wrapped = escape_wrapped("a long\tline of text", 8);
+/// assert_eq!(wrapped, "a long\\\n\\tline \\\nof text");
+/// let options = Options {
+///     trim_line_continuation: true,
+///     ..Options::new()
+/// };
+/// assert_eq!(options.unescape(&wrapped).unwrap(), "a long\tline of text");
+/// ```
+#[inline]
+pub fn escape_wrapped(s: &str, width: usize) -> String {
+    EscapeStyle::new().escape_wrapped(s, width)
+}
+
+/// Escapes only the backslashes of the string, the least escaping for which
+/// the default unescaper reproduces it.
+///
+/// Every character other than a backslash is read as-is by the unescaper,
+/// including quotes and control characters, so they're kept literally.
+/// Strings without backslashes are returned borrowed.
+///
+/// ```
+/// # use unescape_zero_copy::escape_minimal;
+/// assert_eq!(escape_minimal("C:\\tmp\n\"x\""), "C:\\\\tmp\n\"x\"");
+/// ```
+#[inline]
+pub fn escape_minimal(s: &str) -> Cow<'_, str> {
+    escape_with(s, |_| false)
+}
+
+/// Escapes the string so that unescaping with the dialect (through
+/// [`Options::unescape`](crate::Options::unescape)) reproduces it.
+///
+/// Only the escape sequences the dialect recognizes are used: named escapes
+/// where available, and hex or Unicode escapes for other control characters.
+/// Non-ASCII characters are kept as-is, and strings without any characters to
+/// escape are returned borrowed. With [`Dialect::C`], this is the same as
+/// [`escape`]; with [`Dialect::Postgres`], quotes are doubled rather than
+/// escaped.
+///
+/// ```
+/// # use unescape_zero_copy::{escape_dialect, Dialect};
+/// assert_eq!(escape_dialect("it's\x01", Dialect::Json), r"it's\u0001");
+/// assert_eq!(escape_dialect("it's\x01", Dialect::Postgres), r"it''s\x01");
+/// ```
+#[inline]
+pub fn escape_dialect(s: &str, dialect: Dialect) -> Cow<'_, str> {
+    EscapeStyle::new().escape_dialect(s, dialect)
+}
+
+/// Escapes the string into ASCII for JSON, with every non-ASCII character as
+/// `\uXXXX`, for systems which don't accept raw UTF-8.
+///
+/// This is the ASCII-safe form of [`escape_dialect`] with [`Dialect::Json`]:
+/// characters outside the Basic Multilingual Plane (such as emoji) are written
+/// as a UTF-16 surrogate pair, which unescaping with the JSON dialect combines
+/// back into one character. Strings without any characters to escape are
+/// returned borrowed.
+///
+/// ```
+/// # use unescape_zero_copy::escape_json_ascii;
+/// assert_eq!(escape_json_ascii("caf\u{e9} \u{1F600}\n"), r"caf\u00e9 \ud83d\ude00\n");
+/// ```
+#[inline]
+pub fn escape_json_ascii(s: &str) -> Cow<'_, str> {
+    EscapeStyle::new().escape_json_ascii(s)
+}
+
+/// Converts the string from the escape sequences of one dialect to those of
+/// another, e.g. from a JSON string to a Rust string literal.
+///
+/// The string is unescaped with the `from` dialect (through
+/// [`Options::unescape`]), then escaped for the `to` dialect as
+/// [`escape_dialect`] does; errors are those of unescaping.
+///
+/// ```
+/// # use unescape_zero_copy::{reescape, Dialect};
+/// assert_eq!(
+///     reescape(r"tab\x09\101", Dialect::C, Dialect::Json).unwrap(),
+///     r"tab\tA"
+/// );
+/// ```
+#[inline]
+pub fn reescape(s: &str, from: Dialect, to: Dialect) -> Result<String, Error> {
+    EscapeStyle::new().reescape(s, from, to)
+}
+
+/// Escapes the characters of the string for which the function returns
+/// `true`, as with [`escape_char`].
+///
+/// Backslashes are always escaped, regardless of the function, so the default
+/// unescaper can reproduce the string. Strings without any characters to
+/// escape are returned borrowed.
+#[inline]
+pub fn escape_with<F>(s: &str, should_escape: F) -> Cow<'_, str>
+where
+    F: FnMut(char) -> bool,
+{
+    EscapeStyle::new().escape_with(s, should_escape)
+}
+
+/// Escapes the characters of the string for which `should_escape` returns
+/// `true` (and backslashes) by pushing them with `push`, only allocating once
+/// a character to escape is found.
+pub(crate) fn escape_by<F, P>(s: &str, mut should_escape: F, mut push: P) -> Cow<'_, str>
+where
+    F: FnMut(char) -> bool,
+    P: FnMut(&mut String, char),
+{
+    let mut out: Option<String> = None;
+    for (idx, c) in s.char_indices() {
+        let escaped = c == '\\' || should_escape(c);
+        let out = match &mut out {
+            Some(out) if escaped => out,
+            Some(out) => {
+                out.push(c);
+                continue;
+            }
+            None if escaped => out.insert({
+                let mut buf = String::with_capacity(s.len() + 4);
+                buf.push_str(&s[..idx]);
+                buf
+            }),
+            None => continue,
+        };
+        push(out, c);
+        if c == '\0' {
+            disambiguate_nul(out, &s[idx + 1..]);
+        }
+    }
+    out.map_or(Cow::Borrowed(s), Cow::Owned)
+}
+
+/// Replaces a `\0` escape just pushed onto the string with `\x00` if a digit
+/// follows it, which would otherwise be read as part of an octal escape.
+#[inline]
+fn disambiguate_nul(out: &mut String, rest: &str) {
+    if out.ends_with("\\0") && rest.starts_with(|c: char| c.is_ascii_digit()) {
+        out.truncate(out.len() - 2);
+        out.push_str("\\x00");
+    }
+}
+
+/// Escapes the string like [`escape`], but with every hex escape in braces,
+/// e.g. `\x{1}` rather than `\x01`.
+///
+/// A braced escape can't run into a hex digit after it, so unlike `\xNN` it
+/// can't be misread by targets which read `\x` digits greedily. Braced `\x`
+/// escapes are understood by Perl and C++23, but not by C (nor by the default
+/// unescaper); to read them back, enable
+/// [`Options::allow_braced_x`](crate::Options::allow_braced_x).
+///
+/// ```
+/// # use unescape_zero_copy::escape_braced;
+/// assert_eq!(escape_braced("\x01f\n"), r"\x{1}f\n");
+/// ```
+#[inline]
+pub fn escape_braced(s: &str) -> Cow<'_, str> {
+    EscapeStyle::new().escape_braced(s)
+}
+
+/// Escapes every character of the string except those in `keep`.
+///
+/// Characters in `keep` are still escaped if [`escape`] would escape them, so
+/// e.g. a kept backslash or quote remains escaped.
+#[inline]
+pub fn escape_except<'a>(s: &'a str, keep: &[char]) -> Cow<'a, str> {
+    escape_with(s, |c| needs_escape(c) || !keep.contains(&c))
+}
+
+/// Escapes the string using only `\u{...}` escapes, apart from `\\` and `\"`.
+///
+/// Every non-ASCII character and every character [`escape`] would escape is
+/// written as `\u{...}` (with lowercase hex digits), giving a canonical,
+/// ASCII-only form which the default unescaper reproduces.
+#[inline]
+pub fn escape_unicode_all(s: &str) -> Cow<'_, str> {
+    EscapeStyle::new().escape_unicode_all(s)
+}
+
+/// Escapes the string like [`escape`], but keeps escape sequences which are
+/// already valid instead of escaping their backslashes again.
+///
+/// This is a heuristic for strings which may or may not have been escaped
+/// already: a backslash is kept if the default unescaper accepts the escape
+/// sequence it starts, and escaped otherwise. This makes the function
+/// idempotent, so escaping twice is the same as escaping once. However, it
+/// can't tell what was intended when the text is ambiguous; e.g. a literal
+/// backslash followed by `n` is kept as the escape sequence `\n`, and will
+/// unescape to a line break.
+pub fn escape_idempotent(s: &str) -> Cow<'_, str> {
+    let mut out: Option<String> = None;
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            if let Ok((_, after)) = default_escape_sequence(&rest[1..]) {
+                let len = rest.len() - after.len();
+                if let Some(out) = &mut out {
+                    out.push_str(&rest[..len]);
+                }
+                rest = after;
+                continue;
+            }
+        }
+        let idx = s.len() - rest.len();
+        rest = &rest[c.len_utf8()..];
+        if c == '\\' || needs_escape(c) {
+            let out = out.get_or_insert_with(|| {
+                let mut buf = String::with_capacity(s.len() + 4);
+                buf.push_str(&s[..idx]);
+                buf
+            });
+            EscapeStyle::new().push_escaped(out, c);
+            if c == '\0' {
+                disambiguate_nul(out, rest);
+            }
+        } else if let Some(out) = &mut out {
+            out.push(c);
+        }
+    }
+    out.map_or(Cow::Borrowed(s), Cow::Owned)
+}
+
+/// Escapes UTF-16 code units, as from Windows or JavaScript APIs, which may
+/// contain unpaired surrogates.
+///
+/// Valid characters (including surrogate pairs) are escaped as [`escape`]
+/// does, and each unpaired surrogate is written as `\u{dxxx}`, so no
+/// information is lost. The default unescaper rejects these surrogates, but
+/// [`Options::escape_fragment`](crate::Options::escape_fragment) produces them
+/// as surrogate halves.
+///
+/// ```
+/// # use unescape_zero_copy::escape_utf16;
+/// assert_eq!(escape_utf16(&[0x61, 0xD83D, 0xDE00, 0xD83D, 0x0A]), "a\u{1F600}\\u{d83d}\\n");
+/// ```
+#[inline]
+pub fn escape_utf16(units: &[u16]) -> String {
+    EscapeStyle::new().escape_utf16(units)
+}
+
+/// Escapes a byte slice into an ASCII-only string, as for displaying binary
+/// data.
+///
+/// Printable ASCII bytes (0x20 to 0x7E) other than backslashes and quotes are
+/// kept as-is; all other bytes are written as `\xNN`. This is inverted by
+/// [`unescape_bytes`](crate::unescape_bytes).
+///
+/// ```
+/// # use unescape_zero_copy::escape_bytes;
+/// assert_eq!(escape_bytes(b"a\"b\xff\0"), r"a\x22b\xff\x00");
+/// ```
+#[inline]
+pub fn escape_bytes(b: &[u8]) -> String {
+    EscapeStyle::new().escape_bytes(b)
+}
+
+/// Escapes an OS string into an ASCII-only string as [`escape_bytes`] does,
+/// for file paths and other OS strings which may not be valid UTF-8.
+///
+/// This is Unix-only, where OS strings are arbitrary bytes, and byte-based:
+/// every byte outside printable ASCII is written as `\xNN`, including those of
+/// valid non-ASCII characters. This is inverted by
+/// [`unescape_os_str`](crate::unescape_os_str).
+///
+/// ```
+/// # use unescape_zero_copy::escape_os_str;
+/// use std::ffi::OsStr;
+/// use std::os::unix::ffi::OsStrExt;
+/// assert_eq!(escape_os_str(OsStr::from_bytes(b"a\xff b")), r"a\xff b");
+/// ```
+#[cfg(all(feature = "std", unix))]
+#[inline]
+pub fn escape_os_str(s: &std::ffi::OsStr) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    escape_bytes(s.as_bytes())
+}
+
+/// Escapes regular expression metacharacters in the string, so that a regex
+/// built from the result matches the string literally.
+///
+/// This targets regex syntax rather than the escape sequences of this crate:
+/// each of `\ . * + ? ( ) [ ] { } ^ $ |` is preceded by a backslash, and all
+/// other characters are kept as-is. Strings without metacharacters are
+/// returned borrowed.
+pub fn escape_regex(s: &str) -> Cow<'_, str> {
+    escape_by(
+        s,
+        |c| {
+            matches!(
+                c,
+                '.' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$' | '|'
+            )
+        },
+        |out, c| {
+            out.push('\\');
+            out.push(c);
+        },
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::{unescape_bytes, unescape_default, StringFragment, Unescape};
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn escapes_special_characters() {
+        assert!(matches!(escape("hello, wörld"), Cow::Borrowed(_)));
+        assert_eq!(escape("a\"b'c\\d"), r#"a\"b\'c\\d"#);
+        assert_eq!(
+            escape("\x07\x08\t\n\r\0\x7F\u{85}"),
+            r"\a\b\t\n\r\0\x7f\x85"
+        );
+        assert_eq!(escape_char('a'), r"\x61");
+        assert_eq!(escape_char('\u{1F600}'), r"\u{1f600}");
+    }
+
+    #[test]
+    fn escapes_nul_by_name_unless_a_digit_follows() {
+        assert_eq!(escape_char('\0'), r"\0");
+        assert_eq!(escape("a\0b\0"), r"a\0b\0");
+        assert_eq!(escape("\x001\x008\x00\x00"), r"\x001\x008\0\0");
+        assert_eq!(escape_canonical("\x007"), r"\x007");
+        assert_eq!(escape_idempotent("\x005\\0"), r"\x005\0");
+        let units: Vec<u16> = "\x009\x00a".encode_utf16().collect();
+        assert_eq!(escape_utf16(&units), r"\x009\0a");
+        // a surrogate pair before the NUL takes two code units
+        let units: Vec<u16> = "\u{1F600}\x001\u{1F600}\x00a".encode_utf16().collect();
+        assert_eq!(escape_utf16(&units), "\u{1F600}\\x001\u{1F600}\\0a");
+        assert_eq!(
+            unescape_default(&escape_utf16(&units)).unwrap(),
+            "\u{1F600}\x001\u{1F600}\x00a"
+        );
+        for s in ["\x001", "\x0012", "\x00\x01", "\x008", "1\x00"] {
+            assert_eq!(unescape_default(&escape(s)).unwrap(), s, "{s:?}");
+        }
+        assert_eq!(escape_dialect("\x001", Dialect::Rust), r"\x001");
+        assert_eq!(escape_dialect("\x001", Dialect::Java), r"\0001");
+    }
+
+    #[test]
+    fn escapes_vertical_tab_and_form_feed_by_name() {
+        assert_eq!(escape("\x0b\x0c"), r"\v\f");
+        assert_eq!(unescape_default(&escape("\x0b\x0c")).unwrap(), "\x0b\x0c");
+        assert_eq!(escape_char('\x0b'), r"\v");
+    }
+
+    #[test]
+    fn escapes_all_but_kept_characters() {
+        assert_eq!(escape_except("abc", &['a', 'c']), r"a\x62c");
+        assert_eq!(escape_except("a\\\"", &['a', '\\', '"']), r#"a\\\""#);
+        assert_eq!(escape_except("é€", &[]), r"\xe9\u{20ac}");
+        assert!(matches!(escape_except("aa", &['a']), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn escapes_to_unicode_only() {
+        assert_eq!(
+            escape_unicode_all("a\n\"\\'é\u{1F600}"),
+            r#"a\u{a}\"\\\u{27}\u{e9}\u{1f600}"#
+        );
+        assert!(matches!(escape_unicode_all("ascii"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn escapes_idempotently() {
+        assert_eq!(escape_idempotent("a\\nb\nc"), r"a\nb\nc");
+        assert_eq!(escape_idempotent(r"C:\tmp\path"), r"C:\tmp\\path");
+        assert_eq!(escape_idempotent(r#"\"x" \x41 \"#), r#"\"x\" \x41 \\"#);
+        assert!(matches!(
+            escape_idempotent(r"\t\u{1F600}"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn escape_idempotent_is_idempotent(s: String) -> bool {
+        let once = escape_idempotent(&s);
+        escape_idempotent(&escape(&s)) == escape(&s) && escape_idempotent(&once) == once
+    }
+
+    #[test]
+    fn quotes_strings() {
+        assert_eq!(quote(""), r#""""#);
+        assert_eq!(quote("it's"), r#""it's""#);
+        assert_eq!(quote("a\"b\\c\n"), r#""a\"b\\c\n""#);
+        let literal = quote(r#"\"C:\dir\" and "\\""#);
+        let inner = &literal[1..literal.len() - 1];
+        assert_eq!(unescape_default(inner).unwrap(), r#"\"C:\dir\" and "\\""#);
+        assert_eq!(quote_with("|a|b|", '|'), r"|\x7ca\x7cb\x7c|");
+        assert_eq!(quote_with("\u{1F600}", '\u{1F600}').len(), 4 + 9 + 4);
+        assert_eq!(UPPER.quote_with("|\x7f", '|'), r"|\x7C\x7F|");
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_quote(s: String) -> bool {
+        ['"', '\'', '`', '|'].into_iter().all(|q| {
+            let quoted = quote_with(&s, q);
+            let inner = &quoted[1..quoted.len() - 1];
+            // the only unescaped quotes are the outer ones
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => drop(chars.next()),
+                    c if c == q => return false,
+                    _ => (),
+                }
+            }
+            quoted.starts_with(q) && quoted.ends_with(q) && unescape_default(inner).unwrap() == s
+        })
+    }
+
+    #[test]
+    fn escapes_json_into_ascii() {
+        assert!(matches!(
+            escape_json_ascii("plain 'text'"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            escape_json_ascii("\"a\\b\"\t\0\x7f"),
+            r#"\"a\\b\"\t\u0000\u007f"#
+        );
+        assert_eq!(escape_json_ascii("\u{20AC}"), r"\u20ac");
+        assert_eq!(escape_json_ascii("\u{1F600}"), r"\ud83d\ude00");
+        assert_eq!(
+            UPPER.escape_json_ascii("\u{1F600}\u{10FFFF}"),
+            r"\uD83D\uDE00\uDBFF\uDFFF"
+        );
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_json_ascii(s: String) -> bool {
+        let json = Options {
+            dialect: Dialect::Json,
+            ..Options::new()
+        };
+        let escaped = escape_json_ascii(&s);
+        escaped.is_ascii() && json.unescape(&escaped).unwrap() == s
+    }
+
+    #[test]
+    fn wraps_escaped_lines() {
+        assert_eq!(escape_wrapped("", 5), "");
+        assert_eq!(escape_wrapped("abcd", 5), "abcd");
+        assert_eq!(escape_wrapped("abcde", 5), "abcd\\\ne");
+        // escape sequences stay whole
+        assert_eq!(escape_wrapped("abc\nd", 5), "abc\\\n\\nd");
+        assert_eq!(escape_wrapped("abcd  e", 6), "abcd \\\n\\x20e");
+        assert_eq!(escape_wrapped("\x005", 5), "\\x00\\\n5");
+        // narrower widths can't fit an escape sequence and a line continuation
+        for width in 0..5 {
+            assert_eq!(escape_wrapped("a\x01b", width), "a\\\n\\x01\\\nb");
+        }
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_wrapped(s: String, width: u8) -> bool {
+        let options = Options {
+            trim_line_continuation: true,
+            ..Options::new()
+        };
+        let width = usize::from(width % 40);
+        let wrapped = escape_wrapped(&s, width);
+        wrapped
+            .lines()
+            .all(|line| line.chars().count() <= width.max(5))
+            && options.unescape(&wrapped).unwrap() == s
+    }
+
+    #[test]
+    fn escapes_regex_metacharacters() {
+        assert_eq!(
+            escape_regex(r"a.b*c+d?(e)[f]{g}^h$i|j\k"),
+            r"a\.b\*c\+d\?\(e\)\[f\]\{g\}\^h\$i\|j\\k"
+        );
+        assert!(matches!(escape_regex("plain-text é\n"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn escapes_bytes_to_ascii() {
+        assert_eq!(escape_bytes(b"hi there~"), "hi there~");
+        assert_eq!(escape_bytes(b"\\'\n\x7f\x80"), r"\x5c\x27\x0a\x7f\x80");
+        assert_eq!(escape_bytes("é".as_bytes()), r"\xc3\xa9");
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_bytes_inverts_escape_bytes(b: Vec<u8>) -> bool {
+        let escaped = escape_bytes(&b);
+        escaped.is_ascii() && unescape_bytes(&escaped).unwrap() == b
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_unicode_all(s: String) -> bool {
+        let escaped = escape_unicode_all(&s);
+        escaped.is_ascii() && unescape_default(&escaped).unwrap() == s
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape(s: String) -> bool {
+        unescape_default(&escape(&s)).unwrap() == s
+    }
+
+    #[test]
+    fn escapes_only_backslashes() {
+        assert_eq!(escape_minimal(r"a\b\\"), r"a\\b\\\\");
+        assert!(matches!(
+            escape_minimal("'\"\0\x7f\n\u{1F600}"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            unescape_default(&escape_minimal("\\\n\0")).unwrap(),
+            "\\\n\0"
+        );
+    }
+
+    const UPPER: EscapeStyle = EscapeStyle {
+        uppercase_hex: true,
+    };
+
+    #[test]
+    fn escapes_with_uppercase_hex() {
+        assert_eq!(EscapeStyle::default(), EscapeStyle::new());
+        assert_eq!(UPPER.escape("\x1b[\n"), r"\x1B[\n");
+        assert_eq!(UPPER.escape_char('\u{1f600}'), r"\u{1F600}");
+        assert_eq!(UPPER.escape_braced("\x7f"), r"\x{7F}");
+        assert_eq!(UPPER.escape_unicode_all("\u{e9}"), r"\u{E9}");
+        assert_eq!(UPPER.escape_utf16(&[0xDABC]), r"\u{DABC}");
+        assert_eq!(UPPER.escape_bytes(b"\xfa"), r"\xFA");
+        assert_eq!(UPPER.escape_dialect("\x1f", Dialect::Json), r"\u001F");
+        assert_eq!(UPPER.escape_dialect("\x7f", Dialect::Rust), r"\x7F");
+        assert_eq!(UPPER.escape_dialect("\x1f", Dialect::Postgres), r"\x1F");
+        assert_eq!(UPPER.escape_with("a", |c| c == 'a'), r"\x61");
+        assert_eq!(EscapeStyle::new().escape("\x1b"), r"\x1b");
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_uppercase_escape(s: String) -> bool {
+        unescape_default(&UPPER.escape(&s)).unwrap() == s
+            && unescape_default(&UPPER.escape_unicode_all(&s)).unwrap() == s
+    }
+
+    #[test]
+    fn escapes_with_shortest_sequences() {
+        for (c, len) in [
+            ('\n', 2),
+            ('\x7F', 4),
+            ('\u{80}', 4),
+            ('\u{FF}', 4),
+            ('\u{100}', 6),
+            ('\u{FFFF}', 6),
+            ('\u{10000}', 9),
+            ('\u{10FFFF}', 10),
+        ] {
+            let shortest = escape_char_shortest(c);
+            assert_eq!(shortest.len(), len, "{c:?}");
+            assert!(shortest.len() <= escape_char(c).len(), "{c:?}");
+            assert_eq!(default_escape_sequence(&shortest[1..]), Ok((c, "")));
+        }
+        assert_eq!(escape_char_shortest('\0'), r"\0");
+        assert!(matches!(escape_canonical("plain"), Cow::Borrowed(_)));
+        assert_eq!(UPPER.escape_char_shortest('\u{20ac}'), r"\u20AC");
+        assert_eq!(UPPER.escape_canonical("\u{e9}"), r"\xE9");
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_canonical(s: String) -> bool {
+        let escaped = escape_canonical(&s);
+        escaped.is_ascii() && unescape_default(&escaped).unwrap() == s
+    }
+
+    const BRACED: Options = Options {
+        allow_braced_x: true,
+        ..Options::new()
+    };
+
+    #[test]
+    fn escapes_hex_in_braces() {
+        assert!(matches!(escape_braced("plain"), Cow::Borrowed(_)));
+        assert_eq!(escape_braced("\x7Fa\0\t"), r"\x{7f}a\x{0}\t");
+        assert_eq!(escape_braced("\u{85}Fb'\\"), r"\x{85}Fb\'\\");
+        assert_eq!(escape("\x01f"), r"\x01f");
+        assert_eq!(BRACED.unescape(r"\x{1}f").unwrap(), "\x01f");
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_braced(s: String) -> bool {
+        BRACED.unescape(&escape_braced(&s)).unwrap() == s
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_minimal(s: String) -> bool {
+        unescape_default(&escape_minimal(&s)).unwrap() == s
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_except(s: String) -> bool {
+        unescape_default(&escape_except(&s, &['a', '\\', '\n'])).unwrap() == s
+    }
+
+    #[test]
+    fn escapes_for_dialects() {
+        let s = "a\"b'c\\d\n\x01\x7f\u{85}é";
+        assert_eq!(escape_dialect(s, Dialect::C), escape(s));
+        assert_eq!(
+            escape_dialect(s, Dialect::Json),
+            r#"a\"b'c\\d\n\u0001\u007f\u0085é"#
+        );
+        assert_eq!(
+            escape_dialect(s, Dialect::Toml),
+            escape_dialect(s, Dialect::Json)
+        );
+        assert_eq!(
+            escape_dialect(s, Dialect::Rust),
+            r#"a\"b\'c\\d\n\x01\x7f\u{85}é"#
+        );
+        assert_eq!(
+            escape_dialect(s, Dialect::Minimal),
+            "a\\\"b\\'c\\\\d\n\x01\x7f\u{85}é"
+        );
+        assert_eq!(
+            escape_dialect(s, Dialect::Postgres),
+            r#"a"b''c\\d\n\x01\x7f\u0085é"#
+        );
+        assert_eq!(
+            escape_dialect(s, Dialect::Java),
+            r#"a\"b'c\\d\n\001\177\205é"#
+        );
+        assert!(matches!(
+            escape_dialect("plain", Dialect::Json),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn reescapes_between_dialects() {
+        assert_eq!(reescape(r"\x41", Dialect::C, Dialect::Json).unwrap(), "A");
+        assert_eq!(
+            reescape(r"\x01\u{7f}", Dialect::C, Dialect::Json).unwrap(),
+            r"\u0001\u007f"
+        );
+        assert_eq!(
+            reescape(r#"\"\u0000\n"#, Dialect::Json, Dialect::Rust).unwrap(),
+            r#"\"\0\n"#
+        );
+        assert_eq!(
+            UPPER
+                .reescape(r"\u001b", Dialect::Json, Dialect::Rust)
+                .unwrap(),
+            r"\x1B"
+        );
+        // surrogate pairs are combined, and kept as characters or split again
+        assert_eq!(
+            reescape(r"\uD83D\uDE00", Dialect::Json, Dialect::Rust).unwrap(),
+            "\u{1F600}"
+        );
+        assert_eq!(
+            reescape(r"\U0001F600\u{85}", Dialect::C, Dialect::Toml).unwrap(),
+            "\u{1F600}\\u0085"
+        );
+        assert_eq!(
+            reescape(r"\uD83D", Dialect::Json, Dialect::Rust),
+            Err(Error::InvalidUnicode(0xD83D))
+        );
+        assert_eq!(
+            reescape(r"\x41", Dialect::Json, Dialect::C),
+            Err(Error::UnknownSequence('x'))
+        );
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn reescape_preserves_text(s: String) -> bool {
+        let json = escape_dialect(&s, Dialect::Json);
+        let java = reescape(&json, Dialect::Json, Dialect::Java).unwrap();
+        reescape(&java, Dialect::Java, Dialect::Json).unwrap() == json
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn unescape_inverts_escape_dialect(s: String) -> bool {
+        [
+            Dialect::C,
+            Dialect::Toml,
+            Dialect::Json,
+            Dialect::Rust,
+            Dialect::Minimal,
+            Dialect::Postgres,
+            Dialect::Snailquote,
+            Dialect::Java,
+        ]
+        .into_iter()
+        .all(|dialect| {
+            let options = Options {
+                dialect,
+                ..Options::new()
+            };
+            options.unescape(&escape_dialect(&s, dialect)).unwrap() == s
+        })
+    }
+
+    #[test]
+    fn escapes_utf16_with_lone_surrogates() {
+        let units: Vec<u16> = "a\"\u{1F600}\n".encode_utf16().collect();
+        assert_eq!(escape_utf16(&units), escape("a\"\u{1F600}\n"));
+        assert_eq!(escape_utf16(&[0xDE00, 0x61, 0xD83D]), r"\u{de00}a\u{d83d}");
+        assert_eq!(escape_utf16(&[]), "");
+        let options = Options::new();
+        let escaped = escape_utf16(&[0xD83D, 0x5C]);
+        let mut unescaper = Unescape::new(|s| options.escape_fragment(s), &escaped);
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::HighSurrogate(0xD83D)))
+        );
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Escaped('\\')))
+        );
+    }
+}
@@ -7,16 +7,47 @@
 //! through custom parsers if needed, though it mainly supports C-style escape
 //! escape sequences by default.
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+extern crate alloc;
+
 use core::fmt;
 use core::num::ParseIntError;
+use core::ops::Range;
+use core::str::Utf8Error;
 
 #[cfg(all(feature = "alloc", not(feature = "std")))]
-use alloc::borrow::Cow;
+use alloc::{borrow::Cow, string::String, vec::Vec};
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod escape;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod lint;
+mod options;
+#[cfg(all(feature = "std", unix))]
+pub use escape::escape_os_str;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use escape::{
+    escape, escape_braced, escape_bytes, escape_canonical, escape_char, escape_char_shortest,
+    escape_dialect, escape_except, escape_idempotent, escape_json_ascii, escape_minimal,
+    escape_regex, escape_unicode_all, escape_utf16, escape_with, escape_wrapped, quote, quote_with,
+    reescape, EscapeStyle,
+};
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use lint::{lint, Lint, LintKind};
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use options::unescape_snailquote;
+pub use options::{
+    validate_escape_at, Dialect, EscapeSpec, InvalidAction, Options, TrailingPolicy,
+};
+
 /// Errors which may be returned by the unescaper.
+///
+/// More kinds of error may be added in the future, so matches on it need a
+/// wildcard arm.
 #[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum Error {
     /// Error type for a string ending in a backslash without a following escape
     /// sequence.
@@ -30,6 +61,45 @@ pub enum Error {
     UnknownSequence(char),
     /// Errors from parsing Unicode hexadecimal numbers.
     ParseIntError(ParseIntError),
+    /// Error type for byte input which isn't valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// Error type for an escape sequence starting with a decimal digit which
+    /// isn't an octal digit (i.e. `\8` or `\9`).
+    InvalidOctalDigit(char),
+    /// Error type for a NUL character, when rejected by
+    /// [`Options::reject_nul`].
+    NulByte,
+    /// Error type for a Unicode escape sequence with a character code too
+    /// large to represent (e.g. `\u{100000000}`), or with too many digits to
+    /// be reasonable.
+    CodePointOutOfRange,
+    /// Error type for a character rejected by [`unescape_validated`].
+    DisallowedChar(char),
+    /// Error type for input exceeding [`Options::max_input_len`],
+    /// [`Options::max_escapes`], or [`Options::max_consecutive_escapes`].
+    LimitExceeded,
+    /// Error type for an escape sequence producing a Unicode noncharacter,
+    /// when rejected by [`Options::reject_noncharacters`].
+    Noncharacter(u32),
+    /// Error type for a fixed-width hex escape sequence interrupted by a
+    /// character which isn't a hex digit (e.g. `\x4g`, or the backslash of
+    /// `\x4\n`).
+    InvalidHexDigit(char),
+}
+
+impl Error {
+    /// Whether the error is from the string ending partway through an escape
+    /// sequence, so it might succeed with more input.
+    ///
+    /// This is true for [`Error::IncompleteSequence`] and
+    /// [`Error::IncompleteUnicode`]; streaming parsers can wait for more data
+    /// on these errors, and fail on any others. A fixed-width escape sequence
+    /// interrupted by a non-digit (e.g. `\x4g`) can't be completed by more
+    /// data, so it's an [`Error::InvalidHexDigit`] instead.
+    #[inline]
+    pub const fn is_incomplete(&self) -> bool {
+        matches!(self, Self::IncompleteSequence | Self::IncompleteUnicode)
+    }
 }
 
 impl From<ParseIntError> for Error {
@@ -38,6 +108,12 @@ impl From<ParseIntError> for Error {
         Error::ParseIntError(this)
     }
 }
+impl From<Utf8Error> for Error {
+    #[inline]
+    fn from(this: Utf8Error) -> Self {
+        Error::InvalidUtf8(this)
+    }
+}
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -48,16 +124,106 @@ impl fmt::Display for Error {
             Self::InvalidUnicode(code) => write!(f, "invalid Unicode character code {code}"),
             Self::UnknownSequence(ch) => write!(f, "unknown escape sequence starting with `{ch}`"),
             Self::ParseIntError(err) => write!(f, "error parsing integer: {err}"),
+            Self::InvalidUtf8(err) => write!(f, "invalid UTF-8: {err}"),
+            Self::InvalidOctalDigit(ch) => write!(f, "`{ch}` is not a valid octal digit"),
+            Self::NulByte => f.write_str("unexpected NUL character"),
+            Self::CodePointOutOfRange => f.write_str("Unicode character code out of range"),
+            Self::DisallowedChar(ch) => write!(f, "disallowed character {ch:?}"),
+            Self::LimitExceeded => f.write_str("input exceeds the configured limits"),
+            Self::Noncharacter(code) => write!(f, "Unicode noncharacter {code:#X}"),
+            Self::InvalidHexDigit(ch) => {
+                write!(f, "`{ch}` is not a hex digit in Unicode escape sequence")
+            }
         }
     }
 }
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
+/// Wraps the error in an [`std::io::Error`] of kind
+/// [`InvalidData`](std::io::ErrorKind::InvalidData).
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    #[inline]
+    fn from(this: Error) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, this)
+    }
+}
+
+/// An error along with the span of the input it occurred in.
+///
+/// The span is a range of byte offsets into the original string, covering the
+/// backslash of the bad escape sequence and the character after it (if any).
+/// It converts directly into the source spans used by diagnostic rendering
+/// crates; with the `miette` feature, this implements `miette::Diagnostic`,
+/// labelling the span so the bad escape sequence is underlined once the
+/// source string is attached to the report.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedError<E = Error> {
+    /// The error from the escape sequence parser.
+    pub error: E,
+    /// The byte range of the bad escape sequence in the original string.
+    pub span: Range<usize>,
+}
+
+impl<E: fmt::Display> fmt::Display for SpannedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at bytes {}..{}",
+            self.error, self.span.start, self.span.end
+        )
+    }
+}
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for SpannedError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+#[cfg(feature = "miette")]
+impl<E: std::error::Error + 'static> miette::Diagnostic for SpannedError<E> {
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let label =
+            miette::LabeledSpan::new_with_span(Some(self.error.to_string()), self.span.clone());
+        Some(Box::new(core::iter::once(label)))
+    }
+}
+
+/// An error from either unescaping or a callback, as returned by
+/// [`unescape_each`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum CallbackError<E> {
+    /// An error from unescaping the string.
+    Unescape(Error),
+    /// An error returned by the callback.
+    Callback(E),
+}
+
+impl<E: fmt::Display> fmt::Display for CallbackError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unescape(err) => err.fmt(f),
+            Self::Callback(err) => err.fmt(f),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for CallbackError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Unescape(err) => Some(err),
+            Self::Callback(err) => Some(err),
+        }
+    }
+}
 
 /// A fragment of an unescaped string.
 ///
 /// This is either the largest string slice between escape sequences or the
-/// result of parsing an escape sequence.
+/// result of parsing an escape sequence. More kinds of fragment may be added
+/// in the future, so matches on it need a wildcard arm.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
 pub enum StringFragment<'a> {
     /// A string slice between escape sequences.
     Raw(&'a str),
@@ -69,6 +235,49 @@ pub enum StringFragment<'a> {
     /// Lua) have an escape sequence that trims out whitespace or otherwise
     /// doesn't produce a visible character.
     Empty,
+    /// A UTF-16 high surrogate from an escape sequence, left for the caller to
+    /// combine (e.g. with [`join_surrogates`]).
+    ///
+    /// None of the default escape sequences do this; it's produced by
+    /// [`Options::escape_fragment`]. Since surrogates can't be part of a
+    /// string, unescaping into one replaces this with U+FFFD.
+    HighSurrogate(u16),
+    /// A UTF-16 low surrogate from an escape sequence, as with
+    /// [`StringFragment::HighSurrogate`].
+    LowSurrogate(u16),
+    /// An unrecognized escape sequence kept as its original text, including
+    /// the backslash, so it can be written back out unchanged.
+    ///
+    /// None of the default escape sequences do this; it's produced by
+    /// [`Options::escape_fragment`] with [`Options::preserve_unknown`] or
+    /// [`Options::retain_backslash`].
+    /// Escape sequence parsers may return any text here, as
+    /// [`Unescape::next_fragment`] replaces it with the text the sequence was
+    /// parsed from. Unescaping into a string keeps the text as-is.
+    Verbatim(&'a str),
+}
+
+/// The text a [`StringFragment`] stands for in an unescaped string.
+pub(crate) enum FragmentText<'a> {
+    Str(&'a str),
+    Char(char),
+    Empty,
+}
+
+impl<'a> StringFragment<'a> {
+    /// The text this fragment stands for in an unescaped string: raw and
+    /// verbatim text as-is, and surrogate halves as U+FFFD.
+    #[inline]
+    pub(crate) fn text(self) -> FragmentText<'a> {
+        match self {
+            Self::Raw(s) | Self::Verbatim(s) => FragmentText::Str(s),
+            Self::Escaped(c) => FragmentText::Char(c),
+            Self::HighSurrogate(_) | Self::LowSurrogate(_) => {
+                FragmentText::Char(char::REPLACEMENT_CHARACTER)
+            }
+            Self::Empty => FragmentText::Empty,
+        }
+    }
 }
 
 impl From<char> for StringFragment<'_> {
@@ -93,15 +302,225 @@ impl<'a> From<&'a str> for StringFragment<'a> {
     }
 }
 
-#[inline]
-fn unicode_char(s: &str, chars: usize) -> Result<(char, &str), Error> {
-    if s.len() < chars {
-        Err(Error::IncompleteUnicode)
-    } else {
-        let num = u32::from_str_radix(&s[0..chars], 16)?;
-        let ch = char::from_u32(num).ok_or(Error::InvalidUnicode(num))?;
-        Ok((ch, &s[chars..]))
+/// A fragment of an unescaped string, with raw fragments as bytes.
+///
+/// This is the same as [`StringFragment`], for feeding raw fragments to
+/// byte-oriented APIs. Raw fragments are always valid UTF-8.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum StringFragmentBytes<'a> {
+    /// The bytes of a string slice between escape sequences.
+    Raw(&'a [u8]),
+    /// An unescaped character from an escape sequence.
+    Escaped(char),
+    /// An escape sequence that produced no character.
+    Empty,
+    /// A UTF-16 high surrogate from an escape sequence.
+    HighSurrogate(u16),
+    /// A UTF-16 low surrogate from an escape sequence.
+    LowSurrogate(u16),
+    /// The bytes of an unrecognized escape sequence kept as its original text.
+    Verbatim(&'a [u8]),
+}
+
+impl<'a> From<StringFragment<'a>> for StringFragmentBytes<'a> {
+    #[inline]
+    fn from(this: StringFragment<'a>) -> Self {
+        match this {
+            StringFragment::Raw(s) => Self::Raw(s.as_bytes()),
+            StringFragment::Escaped(ch) => Self::Escaped(ch),
+            StringFragment::Empty => Self::Empty,
+            StringFragment::HighSurrogate(unit) => Self::HighSurrogate(unit),
+            StringFragment::LowSurrogate(unit) => Self::LowSurrogate(unit),
+            StringFragment::Verbatim(s) => Self::Verbatim(s.as_bytes()),
+        }
+    }
+}
+
+/// The syntactic form of an escape sequence.
+///
+/// Forms are classified by the C-style syntax of the default unescaper (see
+/// [`default_escape_sequence`]); custom escape sequence parsers are described
+/// by whichever form their syntax most closely resembles.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum EscapeForm {
+    /// A letter standing in for another character, such as `\n`.
+    Named,
+    /// A hexadecimal escape, `\xNN` or `\x{NN...}`.
+    HexShort,
+    /// A four-digit Unicode escape, `\uNNNN`.
+    HexUnicode4,
+    /// An eight-digit Unicode escape, `\UNNNNNNNN`.
+    HexUnicode8,
+    /// A variable-length Unicode escape, `\u{NN...}`.
+    BracedUnicode,
+    /// An octal escape, such as `\101`.
+    Octal,
+    /// A backslash followed by a line break.
+    LineContinuation,
+    /// A character escaping itself, such as `\\` or `\"`.
+    SelfEscape,
+}
+
+impl EscapeForm {
+    fn of(sequence: &str) -> Self {
+        let mut chars = sequence.chars();
+        match chars.next() {
+            Some('x' | 'X') => Self::HexShort,
+            Some('u') if chars.next() == Some('{') => Self::BracedUnicode,
+            Some('u') => Self::HexUnicode4,
+            Some('U') => Self::HexUnicode8,
+            Some('0'..='7') => Self::Octal,
+            Some('\r' | '\n') => Self::LineContinuation,
+            Some('\\' | '\'' | '"' | '/') => Self::SelfEscape,
+            _ => Self::Named,
+        }
+    }
+}
+
+/// Looks up the character produced by a single-character escape sequence of
+/// the default unescaper.
+///
+/// Takes the character following the backslash, e.g. `'n'` for `\n`, and
+/// returns the character it unescapes to. Sequences which take further input
+/// (hexadecimal, Unicode, and octal escapes) return `None`, as do unknown
+/// escape sequences.
+///
+/// ```
+/// # use unescape_zero_copy::simple_escape;
+/// const NEWLINE: Option<char> = simple_escape('n');
+/// assert_eq!(NEWLINE, Some('\n'));
+/// assert_eq!(simple_escape('x'), None);
+/// ```
+pub const fn simple_escape(c: char) -> Option<char> {
+    match c {
+        'a' => Some('\x07'),
+        'b' => Some('\x08'),
+        'f' => Some('\x0C'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        'v' => Some('\x0B'),
+        '\\' | '\'' | '\"' | '/' => Some(c),
+        '\r' | '\n' => Some(c),
+        _ => None,
+    }
+}
+
+/// Combines a UTF-16 surrogate pair into the character it encodes.
+///
+/// Returns [`Error::InvalidUnicode`] with the offending code if `high` isn't a
+/// high surrogate (U+D800 to U+DBFF) or `low` isn't a low surrogate (U+DC00 to
+/// U+DFFF).
+///
+/// ```
+/// # use unescape_zero_copy::{join_surrogates, Error};
+/// assert_eq!(join_surrogates(0xD83D, 0xDE00), Ok('\u{1F600}'));
+/// assert_eq!(join_surrogates(0xD83D, 0x41), Err(Error::InvalidUnicode(0x41)));
+/// ```
+pub fn join_surrogates(high: u32, low: u32) -> Result<char, Error> {
+    if !(0xD800..0xDC00).contains(&high) {
+        return Err(Error::InvalidUnicode(high));
+    }
+    if !(0xDC00..0xE000).contains(&low) {
+        return Err(Error::InvalidUnicode(low));
+    }
+    char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+        .ok_or(Error::InvalidUnicode(high))
+}
+
+/// A string stored inline in a fixed-size buffer, as produced by
+/// [`unescape_simple`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InlineStr<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> InlineStr<N> {
+    /// Borrows the string.
+    pub const fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.buf.split_at(self.len).0) {
+            Ok(s) => s,
+            // only ever built from whole characters
+            Err(_) => "",
+        }
+    }
+}
+
+impl<const N: usize> core::ops::Deref for InlineStr<N> {
+    type Target = str;
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Decodes the UTF-8 character starting at the index of the valid UTF-8 bytes.
+const fn decode_char(bytes: &[u8], idx: usize) -> char {
+    let first = bytes[idx] as u32;
+    let (len, mut code) = match first {
+        0x00..=0x7F => (1, first),
+        0xC0..=0xDF => (2, first & 0x1F),
+        0xE0..=0xEF => (3, first & 0x0F),
+        _ => (4, first & 0x07),
+    };
+    let mut i = 1;
+    while i < len {
+        code = (code << 6) | (bytes[idx + i] as u32 & 0x3F);
+        i += 1;
+    }
+    match char::from_u32(code) {
+        Some(ch) => ch,
+        None => char::REPLACEMENT_CHARACTER,
+    }
+}
+
+/// Unescapes a string containing only simple escape sequences into a buffer
+/// of `N` bytes, usable in `const` contexts.
+///
+/// Only the single-character escape sequences of [`simple_escape`] are
+/// supported; any other escape sequence (including hexadecimal, Unicode, and
+/// octal ones) returns [`Error::UnknownSequence`]. This allows unescaping
+/// constants at compile time, without a build script or procedural macro.
+///
+/// # Panics
+///
+/// Panics if the unescaped string doesn't fit in `N` bytes. The length of the
+/// input is always enough.
+///
+/// ```
+/// # use unescape_zero_copy::{unescape_simple, InlineStr};
+/// const GREETING: InlineStr<16> = match unescape_simple(r"Hello,\tworld!\n") {
+///     Ok(s) => s,
+///     Err(_) => panic!("bad escape sequence"),
+/// };
+/// assert_eq!(GREETING.as_str(), "Hello,\tworld!\n");
+/// ```
+pub const fn unescape_simple<const N: usize>(s: &str) -> Result<InlineStr<N>, Error> {
+    let bytes = s.as_bytes();
+    let mut buf = [0; N];
+    let (mut read, mut len) = (0, 0);
+    while read < bytes.len() {
+        let mut byte = bytes[read];
+        read += 1;
+        if byte == b'\\' {
+            if read == bytes.len() {
+                return Err(Error::IncompleteSequence);
+            }
+            let next = decode_char(bytes, read);
+            match simple_escape(next) {
+                // all simple escapes are ASCII
+                Some(ch) => byte = ch as u8,
+                None => return Err(Error::UnknownSequence(next)),
+            }
+            read += 1;
+        }
+        assert!(len < N, "unescaped string doesn't fit in the buffer");
+        buf[len] = byte;
+        len += 1;
     }
+    Ok(InlineStr { buf, len })
 }
 
 /// The default unescaper, focusing on C-style escape sequences.
@@ -122,50 +541,43 @@ fn unicode_char(s: &str, chars: usize) -> Result<(char, &str), Error> {
 /// * `\/` to a slash (unescaped per ECMAScript).
 /// * `\` followed by a new line keeps the same new line.
 /// * `\xNN` to the Unicode character in the two hex digits.
-/// * `\uNNNN` as above, but with four hex digits.
+/// * `\uNNNN` as above, but with four hex digits; a high surrogate
+///   followed by a `\uNNNN` low surrogate is combined into one character.
 /// * `\UNNNNNNNN` as above, but with eight hex digits.
 /// * `\u{NN...}` as above, but with variable hex digits.
 /// * octal sequences are decoded to the Unicode character.
+///
+/// Every numeric escape sequence gives a Unicode code point, never a raw byte.
+/// Up to U+00FF, these coincide with Latin-1, so e.g. `\xE9`, `\u00E9`,
+/// `\u{e9}`, and `\351` are interchangeable, all decoding to U+00E9 (`é`).
 pub fn default_escape_sequence(s: &str) -> Result<(char, &str), Error> {
-    let mut chars = s.chars();
-    let next = chars.next().ok_or(Error::IncompleteSequence)?;
-    match next {
-        'a' => Ok(('\x07', chars.as_str())),
-        'b' => Ok(('\x08', chars.as_str())),
-        'f' => Ok(('\x0C', chars.as_str())),
-        'n' => Ok(('\n', chars.as_str())),
-        'r' => Ok(('\r', chars.as_str())),
-        't' => Ok(('\t', chars.as_str())),
-        'v' => Ok(('\x0B', chars.as_str())),
-        '\\' | '\'' | '\"' | '/' => Ok((next, chars.as_str())),
-        '\r' | '\n' => Ok((next, chars.as_str())),
-        'x' => unicode_char(chars.as_str(), 2),
-        'u' => {
-            let s = chars.as_str();
-            if chars.next() == Some('{') {
-                let s = chars.as_str();
-                let size = chars.by_ref().take_while(|n| *n != '}').count();
-                let num = u32::from_str_radix(&s[0..size], 16)?;
-                let ch = char::from_u32(num).ok_or(Error::InvalidUnicode(num))?;
-                Ok((ch, chars.as_str()))
-            } else {
-                unicode_char(s, 4)
-            }
-        }
-        'U' => unicode_char(chars.as_str(), 8),
-        _ => {
-            let count = s.chars().take_while(|n| n.is_digit(8)).count().min(3);
-            if count > 0 {
-                let num = u32::from_str_radix(&s[0..count], 8)?;
-                let ch = char::from_u32(num).ok_or(Error::InvalidUnicode(num))?;
-                Ok((ch, &s[count..]))
-            } else {
-                Err(Error::UnknownSequence(next))
-            }
-        }
+    match Options::new().parse_sequence(s)? {
+        (Some(ch), rem) => Ok((ch, rem)),
+        // only `Options::on_invalid_unicode` skips escape sequences
+        (None, _) => unreachable!("default options skipped an escape sequence"),
     }
 }
 
+/// Returns the length in bytes of the escape sequence of the default
+/// unescaper at the start of the string, including its backslash.
+///
+/// Like [`default_escape_sequence`], this is called after a backslash has been
+/// found, for tokenizers which advance through slices themselves. The escape
+/// sequence must still be valid, so errors are the same as for
+/// [`default_escape_sequence`].
+///
+/// ```
+/// # use unescape_zero_copy::escape_len;
+/// let s = r"\u{1F600} rest";
+/// let len = escape_len(&s[1..]).unwrap();
+/// assert_eq!(&s[len..], " rest");
+/// ```
+#[inline]
+pub fn escape_len(after_backslash: &str) -> Result<usize, Error> {
+    let (_, rem) = default_escape_sequence(after_backslash)?;
+    Ok(1 + after_backslash.len() - rem.len())
+}
+
 #[inline]
 fn non_empty(s: &str) -> Option<&str> {
     if s.is_empty() {
@@ -175,18 +587,25 @@ fn non_empty(s: &str) -> Option<&str> {
     }
 }
 
+/// Whether the string is a suffix of the other string (not just equal to its
+/// end), as escape sequence parsers should return; an empty string always is.
 #[inline]
-fn split_at_escape(s: &str) -> (Option<&str>, Option<&str>) {
-    if let Some((first, last)) = s.split_once('\\') {
-        (
-            non_empty(first),
-            // make sure this one is non-`None` to correctly error on incomplete
-            // escape sequences (i.e. strings ending in an unescaped backslash)
-            Some(last),
-        )
-    } else {
-        (non_empty(s), None)
-    }
+fn is_suffix(suffix: &str, of: &str) -> bool {
+    suffix.is_empty()
+        || of
+            .len()
+            .checked_sub(suffix.len())
+            .is_some_and(|start| core::ptr::eq(of.as_bytes()[start..].as_ptr(), suffix.as_ptr()))
+}
+
+/// Returns the byte offset of the first backslash in the string at or after
+/// `from`, or the length of the string if there are none.
+#[inline]
+fn find_escape(s: &str, from: usize) -> usize {
+    s.as_bytes()[from..]
+        .iter()
+        .position(|&b| b == b'\\')
+        .map_or(s.len(), |idx| from + idx)
 }
 
 /// An iterator producing unescaped characters of a string.
@@ -200,10 +619,37 @@ where
     F: FnMut(&'a str) -> Result<(C, &'a str), E>,
     C: From<char>,
 {
-    bare: Option<&'a str>,
-    escaped: Option<&'a str>,
-    rem: Option<&'a str>,
+    /// The whole string being unescaped, or the remainder returned by the
+    /// escape sequence parser if that wasn't a suffix of it.
+    s: &'a str,
+    /// The number of bytes consumed before `s`, which is nonzero only after
+    /// continuing from a remainder which wasn't a suffix.
+    offset: usize,
+    /// The byte offset of the next character to read.
+    pos: usize,
+    /// The byte offset of the next backslash at or after `pos`, or the length
+    /// of the string if there are none.
+    next_escape: usize,
+    /// Whether an escape sequence failed to parse, which ends the iterator.
+    failed: bool,
+    /// The byte range of the escape sequence of the last item, if it was
+    /// parsed from one.
+    last_escape: Option<Range<usize>>,
     escape_sequence: F,
+    peeked: Option<Peeked<'a, C, E>>,
+}
+
+/// A character decoded ahead by [`Unescape::peek`], along with the state of
+/// the unescaper after it, which is restored by the next call to `next`.
+#[derive(Clone, Debug)]
+struct Peeked<'a, C, E> {
+    item: Result<C, E>,
+    s: &'a str,
+    offset: usize,
+    pos: usize,
+    next_escape: usize,
+    failed: bool,
+    last_escape: Option<Range<usize>>,
 }
 
 impl<'a, F, E, C> Unescape<'a, F, E, C>
@@ -218,16 +664,22 @@ where
     /// and shouldn't check for the presence of one. The tuple in the `Result`
     /// should contain the character returned and the remaining portion of the
     /// string after parsing; e.g. a string `"\nabc"` should return
-    /// `('\n', "abc")`.
+    /// `('\n', "abc")`. The remaining portion should be a suffix of the string
+    /// given to the parser; if it isn't, unescaping continues from it as
+    /// though it replaced the rest of the string, and the escape sequence is
+    /// taken to span the rest of the original string, so positions then count
+    /// on from its end.
     #[inline]
     pub fn new(escape_sequence: F, from: &'a str) -> Self {
-        let rem = non_empty(from);
-        let (bare, escaped) = split_at_escape(from);
         Self {
-            bare,
-            escaped,
-            rem,
+            s: from,
+            offset: 0,
+            pos: 0,
+            next_escape: find_escape(from, 0),
+            failed: false,
+            last_escape: None,
             escape_sequence,
+            peeked: None,
         }
     }
 
@@ -236,7 +688,156 @@ where
     /// If all the string has been consumed, returns `None`.
     #[inline]
     pub fn remainder(&self) -> Option<&'a str> {
-        self.rem
+        non_empty(&self.s[self.pos..])
+    }
+
+    /// Returns the next character without consuming it.
+    ///
+    /// The character (or error) is decoded once and handed back by the next
+    /// call to [`next`](Iterator::next), so the escape sequence parser isn't
+    /// called again for it. Until then, the [`remainder`](Self::remainder)
+    /// and [`position`](Self::position) still include it.
+    ///
+    /// ```
+    /// # use unescape_zero_copy::{default_escape_sequence, UnescapeDefault};
+    /// let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"\x41b");
+    /// assert_eq!(unescaper.peek(), Some(&Ok('A')));
+    /// assert_eq!(unescaper.position(), 0);
+    /// assert_eq!(unescaper.next(), Some(Ok('A')));
+    /// assert_eq!(unescaper.position(), 4);
+    /// ```
+    pub fn peek(&mut self) -> Option<&Result<C, E>> {
+        if self.peeked.is_none() {
+            let (s, offset) = (self.s, self.offset);
+            let (pos, next_escape, failed) = (self.pos, self.next_escape, self.failed);
+            let last_escape = self.last_escape.clone();
+            let item = self.next()?;
+            self.peeked = Some(Peeked {
+                item,
+                s: core::mem::replace(&mut self.s, s),
+                offset: core::mem::replace(&mut self.offset, offset),
+                pos: core::mem::replace(&mut self.pos, pos),
+                next_escape: core::mem::replace(&mut self.next_escape, next_escape),
+                failed: core::mem::replace(&mut self.failed, failed),
+                last_escape: core::mem::replace(&mut self.last_escape, last_escape),
+            });
+        }
+        self.peeked.as_ref().map(|peeked| &peeked.item)
+    }
+
+    /// Returns whether the rest of the string has no escape sequences, so the
+    /// iterator only yields its raw characters.
+    ///
+    /// This is a constant-time check of the iterator's state, for taking a
+    /// fast path around the escape sequence machinery; for a new iterator,
+    /// it's whether the whole string is free of backslashes.
+    #[inline]
+    pub fn is_trivial(&self) -> bool {
+        self.failed || self.next_escape == self.s.len()
+    }
+
+    /// Counts the escape sequences in the unparsed remainder of the string,
+    /// without parsing them or advancing the iterator.
+    ///
+    /// Each backslash starts an escape sequence, except one escaped by the
+    /// backslash before it, so e.g. `\\` counts once. The sequences aren't
+    /// checked, so this is an estimate for sizing buffers; invalid sequences
+    /// are counted as well.
+    pub fn count_remaining_escapes(&self) -> usize {
+        let mut rest = &self.s.as_bytes()[self.pos..];
+        let mut count = 0;
+        while let Some(idx) = rest.iter().position(|&b| b == b'\\') {
+            count += 1;
+            // the character after a backslash never starts another sequence
+            rest = rest.get(idx + 2..).unwrap_or_default();
+        }
+        count
+    }
+
+    /// Attaches the span of the escape sequence at the current position to an
+    /// error from the escape sequence parser.
+    fn spanned<T>(&self, error: T) -> SpannedError<T> {
+        let start = self.position();
+        let next = self
+            .s
+            .get(self.pos + 1..)
+            .and_then(|rest| rest.chars().next());
+        SpannedError {
+            error,
+            span: start..start + 1 + next.map_or(0, char::len_utf8),
+        }
+    }
+
+    /// Turns the unescaper into an iterator over characters along with the
+    /// byte range of the original string each came from.
+    ///
+    /// Raw characters span themselves, and escaped characters span their
+    /// whole escape sequence, including the backslash. Errors carry the span of
+    /// the bad escape sequence, as with [`unescape_spanned`].
+    ///
+    /// ```
+    /// # use unescape_zero_copy::{default_escape_sequence, UnescapeDefault};
+    /// let mut spans = UnescapeDefault::new(default_escape_sequence, r"a\x41").spans();
+    /// assert_eq!(spans.next(), Some(Ok((0..1, 'a'))));
+    /// assert_eq!(spans.next(), Some(Ok((1..5, 'A'))));
+    /// assert_eq!(spans.next(), None);
+    /// ```
+    #[inline]
+    pub fn spans(self) -> Spans<'a, F, E, C> {
+        Spans { inner: self }
+    }
+
+    /// Adapts the iterator to return [`std::io::Result`]s, for code built
+    /// around `std::io`.
+    ///
+    /// Errors are wrapped in an [`std::io::Error`] of kind
+    /// [`InvalidData`](std::io::ErrorKind::InvalidData).
+    ///
+    /// ```
+    /// # use unescape_zero_copy::{default_escape_sequence, UnescapeDefault};
+    /// use std::io::ErrorKind;
+    ///
+    /// let mut chars = UnescapeDefault::new(default_escape_sequence, r"a\q").into_io();
+    /// assert_eq!(chars.next().unwrap().unwrap(), 'a');
+    /// assert_eq!(chars.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+    /// assert!(chars.next().is_none());
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_io(self) -> IntoIo<'a, F, E, C> {
+        IntoIo { inner: self }
+    }
+
+    /// Returns the text of the escape sequence which produced the last item,
+    /// including the backslash.
+    ///
+    /// This is `None` if the last item was a raw character or an error, or
+    /// if there hasn't been one yet. Peeking doesn't change the last item.
+    ///
+    /// ```
+    /// # use unescape_zero_copy::{default_escape_sequence, UnescapeDefault};
+    /// let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"a\x41");
+    /// assert_eq!(unescaper.next(), Some(Ok('a')));
+    /// assert_eq!(unescaper.last_escape_text(), None);
+    /// assert_eq!(unescaper.next(), Some(Ok('A')));
+    /// assert_eq!(unescaper.last_escape_text(), Some(r"\x41"));
+    /// ```
+    #[inline]
+    pub fn last_escape_text(&self) -> Option<&'a str> {
+        self.last_escape.clone().map(|span| &self.s[span])
+    }
+
+    /// Returns the number of bytes of the original string consumed so far.
+    ///
+    /// This is the byte offset into the original string where the
+    /// [`remainder`](Self::remainder) starts. If the unescaper stopped on an
+    /// error, this is the offset of the backslash starting the bad sequence.
+    /// After a parser returns a remainder which isn't a suffix of its input,
+    /// this counts on from the end of the original string (see
+    /// [`Unescape::new`]).
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.offset + self.pos
     }
 }
 
@@ -251,27 +852,161 @@ where
     /// Advances the iterator accordingly.
     #[inline]
     pub fn next_fragment(&mut self) -> Option<Result<StringFragment<'a>, E>> {
-        if let Some(frag) = self.bare.take() {
-            self.rem = self.rem.and_then(|rem| non_empty(&rem[frag.len()..]));
+        if self.pos < self.next_escape {
+            // a peeked raw character is part of this fragment
+            self.peeked = None;
+            self.last_escape = None;
+            let frag = &self.s[self.pos..self.next_escape];
+            self.pos = self.next_escape;
             Some(Ok(StringFragment::Raw(frag)))
         } else {
-            self.next().map(|opt| opt.map(StringFragment::from))
+            let fragment = self.next()?.map(StringFragment::from);
+            Some(fragment.map(|fragment| match fragment {
+                StringFragment::Verbatim(text) => {
+                    StringFragment::Verbatim(self.last_escape_text().unwrap_or(text))
+                }
+                fragment => fragment,
+            }))
+        }
+    }
+
+    /// Get the next string fragment, with raw fragments as bytes.
+    ///
+    /// Advances the iterator accordingly.
+    #[inline]
+    pub fn next_fragment_bytes(&mut self) -> Option<Result<StringFragmentBytes<'a>, E>> {
+        self.next_fragment()
+            .map(|frag| frag.map(StringFragmentBytes::from))
+    }
+
+    /// Get the next string fragment along with the form of the escape sequence
+    /// which produced it.
+    ///
+    /// The form is `None` for raw fragments. Advances the iterator accordingly.
+    pub fn next_fragment_detailed(
+        &mut self,
+    ) -> Option<Result<(StringFragment<'a>, Option<EscapeForm>), E>> {
+        let escaped = self.pos == self.next_escape;
+        // the form only depends on the start of the sequence
+        let after = self.s.get(self.pos + 1..).unwrap_or_default();
+        let fragment = self.next_fragment()?;
+        Some(fragment.map(|fragment| (fragment, escaped.then(|| EscapeForm::of(after)))))
+    }
+
+    /// Get the next run of unescaped text, merging consecutive raw fragments
+    /// and escaped characters into one string.
+    ///
+    /// A run ends at the end of the string or at an escape sequence producing
+    /// no character ([`StringFragment::Empty`]), so for parsers which always
+    /// produce a character, the whole rest of the string is one run. Unlike
+    /// [`next_fragment`](Self::next_fragment), this must allocate for any run
+    /// containing an escape sequence; runs of only raw text are borrowed. If an
+    /// error occurs partway through a run, the text of that run is discarded.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn next_coalesced(&mut self) -> Option<Result<Cow<'a, str>, E>> {
+        let mut out: Option<Cow<'a, str>> = None;
+        loop {
+            match self.next_fragment() {
+                None => return out.map(Ok),
+                Some(Err(e)) => return Some(Err(e)),
+                Some(Ok(fragment)) => match fragment.text() {
+                    FragmentText::Str(s) => *out.get_or_insert_with(Cow::default) += s,
+                    FragmentText::Char(c) => out.get_or_insert_with(Cow::default).to_mut().push(c),
+                    FragmentText::Empty => {
+                        if let Some(out) = out {
+                            return Some(Ok(out));
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    /// Splits the rest of the unescaped string on a separator character,
+    /// without unescaping the whole string first.
+    ///
+    /// Separators produced by escape sequences (e.g. `\n` for `'\n'`) split
+    /// the string just like literal ones. As with [`str::split`], separators at
+    /// either end produce empty pieces. If an error occurs, it is returned in
+    /// place of the piece it was found in, and the iterator ends.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn split_decoded(self, sep: char) -> SplitDecoded<'a, F, E, C> {
+        SplitDecoded {
+            inner: self,
+            sep,
+            split_raw: true,
+            lines: false,
+            pending: None,
+            finished: false,
+        }
+    }
+
+    /// Splits the rest of the unescaped string into lines, without unescaping
+    /// the whole string first.
+    ///
+    /// Lines end at line feeds, whether literal or produced by escape
+    /// sequences (e.g. `\n` or `\x0a`). As with [`str::lines`], a carriage
+    /// return just before a line feed is dropped, and a line feed at the end
+    /// of the string doesn't produce an empty line after it. Errors are
+    /// returned as with [`split_decoded`](Self::split_decoded).
+    ///
+    /// ```
+    /// # use unescape_zero_copy::{default_escape_sequence, UnescapeDefault};
+    /// let s = "one\\ntwo\\r\\nthree\n";
+    /// let lines = UnescapeDefault::new(default_escape_sequence, s).lines();
+    /// assert_eq!(lines.collect::<Result<Vec<_>, _>>().unwrap(), ["one", "two", "three"]);
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn lines(self) -> SplitDecoded<'a, F, E, C> {
+        SplitDecoded {
+            lines: true,
+            ..self.split_decoded('\n')
+        }
+    }
+
+    /// Splits the rest of the unescaped string into lines as
+    /// [`lines`](Self::lines) does, but only at line feeds produced by escape
+    /// sequences; literal line feeds are kept in the lines.
+    ///
+    /// This suits formats where raw line breaks are only for readability,
+    /// such as a long string wrapped across several lines of a source file.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn escaped_lines(self) -> SplitDecoded<'a, F, E, C> {
+        SplitDecoded {
+            split_raw: false,
+            ..self.lines()
         }
     }
 
     /// Processes the rest of the iterator into a [`Cow`] string.
     ///
     /// Avoids allocation unless any escape sequences were found and had to be
-    /// processed; raw strings are returned as-is.
+    /// processed; raw strings are returned as-is. When the first escape
+    /// sequence is found, enough is allocated for the rest of the input, which
+    /// is an upper bound as long as escape sequences are no shorter than the
+    /// characters they produce (true of the default parser).
     #[cfg(any(feature = "std", feature = "alloc"))]
     pub fn as_cow(&mut self) -> Result<Cow<'a, str>, E> {
-        let mut out = Cow::default();
+        let mut out = Cow::Borrowed("");
         while let Some(fragment) = self.next_fragment().transpose()? {
-            match fragment {
-                StringFragment::Raw(s) => out += s,
-                StringFragment::Escaped(c) => out.to_mut().push(c),
-                StringFragment::Empty => (),
+            let c = match fragment.text() {
+                FragmentText::Str(s) => {
+                    out += s;
+                    continue;
+                }
+                FragmentText::Char(c) => c,
+                FragmentText::Empty => continue,
+            };
+            if let Cow::Borrowed(prefix) = out {
+                let rest = self.s.len() - self.pos;
+                let mut owned = String::with_capacity(prefix.len() + c.len_utf8() + rest);
+                owned.push_str(prefix);
+                out = Cow::Owned(owned);
             }
+            out.to_mut().push(c);
         }
         Ok(out)
     }
@@ -284,41 +1019,51 @@ where
 {
     type Item = Result<C, E>;
     fn next(&mut self) -> Option<Self::Item> {
-        self.bare
-            .as_mut()
-            .and_then(|bare| {
-                let mut chars = bare.chars();
-                let ch = chars.next()?;
-                *bare = chars.as_str();
-                self.rem = self.rem.and_then(|rem| non_empty(&rem[ch.len_utf8()..]));
-                Some(Ok(C::from(ch)))
-            })
-            .or_else(|| {
-                if let Some(s) = self.escaped.take() {
-                    Some(match (self.escape_sequence)(s) {
-                        Ok((ch, rem)) => {
-                            self.rem = non_empty(rem);
-                            let (bare, escaped) = split_at_escape(rem);
-                            self.bare = bare;
-                            self.escaped = escaped;
-                            Ok(ch)
-                        }
-                        Err(e) => {
-                            // assume the error will be reproducible (the escape
-                            // sequence parsers should be deterministic), and any
-                            // state advancement from here would be invalid anyway,
-                            // so abort the unescaper
-                            self.bare = None;
-                            self.escaped = None;
-                            Err(e)
-                        }
-                    })
+        if let Some(peeked) = self.peeked.take() {
+            self.s = peeked.s;
+            self.offset = peeked.offset;
+            self.pos = peeked.pos;
+            self.next_escape = peeked.next_escape;
+            self.failed = peeked.failed;
+            self.last_escape = peeked.last_escape;
+            return Some(peeked.item);
+        }
+        if self.failed {
+            return None;
+        }
+        if self.pos < self.next_escape {
+            let ch = self.s[self.pos..].chars().next()?;
+            self.pos += ch.len_utf8();
+            self.last_escape = None;
+            return Some(Ok(C::from(ch)));
+        }
+        // a backslash at the end of the string is still passed to the parser,
+        // to correctly error on incomplete escape sequences
+        let after = self.s.get(self.pos + 1..)?;
+        Some(match (self.escape_sequence)(after) {
+            Ok((ch, rem)) => {
+                let start = self.pos;
+                if is_suffix(rem, after) {
+                    self.pos = self.s.len() - rem.len();
+                    self.last_escape = Some(start..self.pos);
                 } else {
-                    // in case it was empty but not `None`
-                    self.bare = None;
-                    None
+                    self.offset += self.s.len();
+                    self.s = rem;
+                    self.pos = 0;
+                    self.last_escape = None;
                 }
-            })
+                self.next_escape = find_escape(self.s, self.pos);
+                Ok(ch)
+            }
+            Err(e) => {
+                // assume the error will be reproducible (the escape sequence
+                // parsers should be deterministic), and any state advancement
+                // from here would be invalid anyway, so abort the unescaper
+                self.failed = true;
+                self.last_escape = None;
+                Err(e)
+            }
+        })
     }
 }
 impl<'a, F, E, C> core::iter::FusedIterator for Unescape<'a, F, E, C>
@@ -328,51 +1073,1109 @@ where
 {
 }
 
-// type alias for backwards compatibility
-/// An iterator producing unescaped characters of a string.
-pub type UnescapeDefault<'a> =
-    Unescape<'a, fn(&'a str) -> Result<(char, &'a str), Error>, Error, char>;
-
-/// Unescape the string into a [`Cow`] string.
+/// An iterator over the pieces of an unescaped string, split on a separator
+/// character.
 ///
-/// The function only allocates if any escape sequences were found; otherwise,
-/// the original string is returned unchanged.
-/// More information on the escape sequence parser which is provided here can
-/// be found at [`Unescape::new`].
+/// This is created by [`Unescape::split_decoded`], [`Unescape::lines`], and
+/// [`Unescape::escaped_lines`].
 #[cfg(any(feature = "std", feature = "alloc"))]
-#[inline]
-pub fn unescape<'a, F, E, C>(escape_sequence: F, s: &'a str) -> Result<Cow<'a, str>, E>
+#[derive(Clone, Debug)]
+pub struct SplitDecoded<'a, F, E, C = Option<char>>
 where
     F: FnMut(&'a str) -> Result<(C, &'a str), E>,
     C: From<char>,
-    StringFragment<'a>: From<C>,
 {
-    Unescape::new(escape_sequence, s).as_cow()
+    inner: Unescape<'a, F, E, C>,
+    sep: char,
+    /// Whether literal separators in raw text split the string.
+    split_raw: bool,
+    /// Whether the pieces are lines, with no empty line after a final line
+    /// feed and carriage returns before line feeds dropped.
+    lines: bool,
+    pending: Option<&'a str>,
+    finished: bool,
 }
 
-/// Unescapes the string as [`unescape`] using the default escape sequence
-/// parser.
-///
-/// See [`default_escape_sequence`] for a list of the supported escape
-/// sequences.
 #[cfg(any(feature = "std", feature = "alloc"))]
-#[inline]
-pub fn unescape_default(s: &str) -> Result<Cow<str>, Error> {
-    UnescapeDefault::new(default_escape_sequence, s).as_cow()
+impl<'a, F, E, C> SplitDecoded<'a, F, E, C>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+{
+    /// Finishes a piece ended by a separator.
+    #[inline]
+    fn end_piece(&self, mut piece: String) -> String {
+        if self.lines && piece.ends_with('\r') {
+            piece.pop();
+        }
+        piece
+    }
 }
 
-#[cfg(all(test, feature = "std"))]
-mod test {
-    use super::*;
-    use quickcheck::TestResult;
-    use quickcheck_macros::quickcheck;
-    use std::borrow::Cow;
-
-    #[test]
-    fn borrow_strings_without_escapes() {
-        assert!(matches!(
-            unescape_default("hello").unwrap(),
-            Cow::Borrowed(_)
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, F, E, C> Iterator for SplitDecoded<'a, F, E, C>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+    StringFragment<'a>: From<C>,
+{
+    type Item = Result<String, E>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut piece = String::new();
+        loop {
+            let fragment = match self.pending.take() {
+                // the rest of a raw fragment after a separator
+                Some(raw) => StringFragment::Raw(raw),
+                None => match self.inner.next_fragment() {
+                    Some(Ok(fragment)) => fragment,
+                    Some(Err(e)) => {
+                        self.finished = true;
+                        return Some(Err(e));
+                    }
+                    None => {
+                        self.finished = true;
+                        if self.lines && piece.is_empty() {
+                            return None;
+                        }
+                        return Some(Ok(piece));
+                    }
+                },
+            };
+            match fragment {
+                StringFragment::Raw(s) if self.split_raw => match s.split_once(self.sep) {
+                    Some((before, after)) => {
+                        piece.push_str(before);
+                        self.pending = Some(after);
+                        return Some(Ok(self.end_piece(piece)));
+                    }
+                    None => piece.push_str(s),
+                },
+                StringFragment::Escaped(c) if c == self.sep => {
+                    return Some(Ok(self.end_piece(piece)))
+                }
+                fragment => match fragment.text() {
+                    FragmentText::Str(s) => piece.push_str(s),
+                    FragmentText::Char(c) => piece.push(c),
+                    FragmentText::Empty => (),
+                },
+            }
+        }
+    }
+}
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, F, E, C> core::iter::FusedIterator for SplitDecoded<'a, F, E, C>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+    StringFragment<'a>: From<C>,
+{
+}
+
+/// An iterator over the unescaped characters of a string along with their
+/// spans in the original string.
+///
+/// This is created by [`Unescape::spans`].
+#[derive(Clone, Debug)]
+pub struct Spans<'a, F, E, C = Option<char>>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+{
+    inner: Unescape<'a, F, E, C>,
+}
+
+impl<'a, F, E, C> Iterator for Spans<'a, F, E, C>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+{
+    type Item = Result<(Range<usize>, C), SpannedError<E>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.inner.position();
+        Some(match self.inner.next()? {
+            Ok(ch) => Ok((start..self.inner.position(), ch)),
+            Err(e) => Err(self.inner.spanned(e)),
+        })
+    }
+}
+impl<'a, F, E, C> core::iter::FusedIterator for Spans<'a, F, E, C>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+{
+}
+
+/// An iterator over the unescaped characters of a string, with errors as
+/// [`std::io::Error`]s.
+///
+/// This is created by [`Unescape::into_io`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct IntoIo<'a, F, E, C = Option<char>>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+{
+    inner: Unescape<'a, F, E, C>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, F, E, C> Iterator for IntoIo<'a, F, E, C>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Item = std::io::Result<C>;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(
+            self.inner
+                .next()?
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        )
+    }
+}
+#[cfg(feature = "std")]
+impl<'a, F, E, C> core::iter::FusedIterator for IntoIo<'a, F, E, C>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+}
+
+// type alias for backwards compatibility
+/// An iterator producing unescaped characters of a string.
+pub type UnescapeDefault<'a> =
+    Unescape<'a, fn(&'a str) -> Result<(char, &'a str), Error>, Error, char>;
+
+impl<'a> From<&'a str> for UnescapeDefault<'a> {
+    /// Creates an unescaper using [`default_escape_sequence`], the same as
+    /// `UnescapeDefault::new(default_escape_sequence, s)`.
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self::new(default_escape_sequence, s)
+    }
+}
+
+/// Unescape the string into a [`Cow`] string.
+///
+/// The function only allocates if any escape sequences were found; otherwise,
+/// the original string is returned unchanged.
+/// More information on the escape sequence parser which is provided here can
+/// be found at [`Unescape::new`].
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn unescape<'a, F, E, C>(escape_sequence: F, s: &'a str) -> Result<Cow<'a, str>, E>
+where
+    F: FnMut(&'a str) -> Result<(C, &'a str), E>,
+    C: From<char>,
+    StringFragment<'a>: From<C>,
+{
+    Unescape::new(escape_sequence, s).as_cow()
+}
+
+/// Unescapes the string as [`unescape`] using the default escape sequence
+/// parser.
+///
+/// See [`default_escape_sequence`] for a list of the supported escape
+/// sequences.
+///
+/// The result is borrowed exactly when the string contains no backslashes (see
+/// [`unescape_borrowed`]). Every default escape sequence produces a character,
+/// so any string with an escape sequence is allocated, even if it happens to
+/// unescape to a substring of the input.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn unescape_default(s: &str) -> Result<Cow<'_, str>, Error> {
+    UnescapeDefault::new(default_escape_sequence, s).as_cow()
+}
+
+/// A string stored inline in a buffer of `N` bytes, spilling onto the heap
+/// only if it grows larger, as produced by [`unescape_small`].
+#[cfg(feature = "smallvec")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SmallString<const N: usize = 64> {
+    buf: SmallBuf<N>,
+}
+
+/// The storage of a [`SmallString`], which is inline exactly when the string
+/// is at most `N` bytes long.
+#[cfg(feature = "smallvec")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SmallBuf<const N: usize> {
+    /// The bytes of whole characters, never spilled onto the heap.
+    Inline(smallvec::SmallVec<[u8; N]>),
+    Heap(String),
+}
+
+#[cfg(feature = "smallvec")]
+impl<const N: usize> SmallString<N> {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            buf: SmallBuf::Inline(smallvec::SmallVec::new()),
+        }
+    }
+
+    /// Borrows the string.
+    ///
+    /// An inline string is checked to be UTF-8 on each call, which reads at
+    /// most `N` bytes; a spilled string is borrowed as-is.
+    pub fn as_str(&self) -> &str {
+        match &self.buf {
+            SmallBuf::Inline(buf) => {
+                core::str::from_utf8(buf).expect("inline buffer holds whole characters")
+            }
+            SmallBuf::Heap(s) => s,
+        }
+    }
+
+    /// Returns whether the string was too long for the inline buffer, and
+    /// was moved onto the heap.
+    #[inline]
+    pub fn spilled(&self) -> bool {
+        matches!(self.buf, SmallBuf::Heap(_))
+    }
+
+    fn push_str(&mut self, s: &str) {
+        match &mut self.buf {
+            SmallBuf::Inline(buf) if buf.len() + s.len() <= N => {
+                buf.extend_from_slice(s.as_bytes())
+            }
+            SmallBuf::Inline(buf) => {
+                let mut heap = String::with_capacity(buf.len() + s.len());
+                heap.push_str(
+                    core::str::from_utf8(buf).expect("inline buffer holds whole characters"),
+                );
+                heap.push_str(s);
+                self.buf = SmallBuf::Heap(heap);
+            }
+            SmallBuf::Heap(heap) => heap.push_str(s),
+        }
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<const N: usize> core::ops::Deref for SmallString<N> {
+    type Target = str;
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Unescapes the string as [`unescape_default`] does, into a buffer of `N`
+/// bytes on the stack which only spills onto the heap for longer output.
+///
+/// This avoids allocating for short strings with escape sequences, which
+/// [`unescape_default`] always has to do; [`SmallString`] defaults to a
+/// buffer of 64 bytes.
+///
+/// ```
+/// # use unescape_zero_copy::{unescape_small, SmallString};
+/// let s: SmallString = unescape_small(r"Hello,\tworld!").unwrap();
+/// assert_eq!(&*s, "Hello,\tworld!");
+/// assert!(!s.spilled());
+/// ```
+#[cfg(feature = "smallvec")]
+pub fn unescape_small<const N: usize>(s: &str) -> Result<SmallString<N>, Error> {
+    let mut out = SmallString::new();
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    while let Some(fragment) = unescaper.next_fragment().transpose()? {
+        match fragment.text() {
+            FragmentText::Str(s) => out.push_str(s),
+            FragmentText::Char(c) => out.push_str(c.encode_utf8(&mut [0; 4])),
+            FragmentText::Empty => (),
+        }
+    }
+    Ok(out)
+}
+
+/// Returns the string if unescaping it wouldn't need to allocate, i.e. if it
+/// contains no escape sequences.
+///
+/// This is the case where [`unescape_default`] returns [`Cow::Borrowed`];
+/// `None` means unescaping would allocate (or fail).
+#[inline]
+pub fn unescape_borrowed(s: &str) -> Option<&str> {
+    if s.contains('\\') {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+/// Unescapes the string as [`unescape_default`], reporting the span of the bad
+/// escape sequence on errors.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_spanned(s: &str) -> Result<Cow<'_, str>, SpannedError> {
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    unescaper.as_cow().map_err(|e| unescaper.spanned(e))
+}
+
+/// Unescapes only up to (and including) the first escape sequence of the
+/// string, using the default escape sequence parser.
+///
+/// Returns the unescaped prefix along with the remaining, unprocessed portion
+/// of the string. If there are no escape sequences, the whole string is
+/// returned borrowed with an empty remainder.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn unescape_first(s: &str) -> Result<(Cow<'_, str>, &str), Error> {
+    unescape_n(s, 1)
+}
+
+/// Unescapes only up to (and including) the `n`th escape sequence of the
+/// string, using the default escape sequence parser, to process escape
+/// sequences in batches.
+///
+/// Returns the unescaped prefix along with the remaining, unprocessed portion
+/// of the string, which starts right after the `n`th escape sequence. If the
+/// string has fewer than `n` escape sequences, all of it is unescaped and the
+/// remainder is empty; if it has none, it's returned borrowed. With `n` of
+/// zero, nothing is unescaped.
+///
+/// ```
+/// # use unescape_zero_copy::unescape_n;
+/// let (out, rem) = unescape_n(r"a\tb\nc\rd", 2).unwrap();
+/// assert_eq!((&*out, rem), ("a\tb\n", r"c\rd"));
+/// let (out, rem) = unescape_n(r"a\tb", 2).unwrap();
+/// assert_eq!((&*out, rem), ("a\tb", ""));
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_n(s: &str, n: usize) -> Result<(Cow<'_, str>, &str), Error> {
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    let mut out = Cow::default();
+    let mut escapes = 0;
+    while escapes < n {
+        let Some(fragment) = unescaper.next_fragment().transpose()? else {
+            break;
+        };
+        if !matches!(fragment, StringFragment::Raw(_)) {
+            escapes += 1;
+        }
+        match fragment.text() {
+            FragmentText::Str(s) => out += s,
+            FragmentText::Char(c) => out.to_mut().push(c),
+            FragmentText::Empty => (),
+        }
+    }
+    Ok((out, unescaper.remainder().unwrap_or_default()))
+}
+
+/// Unescapes the string as [`unescape_default`], but treats a trailing
+/// incomplete escape sequence as a need for more input rather than an error.
+///
+/// Returns the unescaped prefix along with the leftover incomplete escape
+/// sequence (starting at its backslash), which should be prepended to the next
+/// chunk of input. The leftover is empty if the whole string was unescaped.
+/// Other errors, such as unknown escape sequences or invalid digits, are still
+/// returned as errors.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_partial(s: &str) -> Result<(Cow<'_, str>, &str), Error> {
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    let mut out = Cow::default();
+    while let Some(fragment) = unescaper.next_fragment() {
+        match fragment.map(StringFragment::text) {
+            Ok(FragmentText::Str(s)) => out += s,
+            Ok(FragmentText::Char(c)) => out.to_mut().push(c),
+            Ok(FragmentText::Empty) => (),
+            Err(e) if e.is_incomplete() => {
+                return Ok((out, unescaper.remainder().unwrap_or_default()));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((out, ""))
+}
+
+/// Unescapes the string as [`unescape_default`], keeping the text unescaped
+/// before an error rather than discarding it.
+///
+/// Returns the unescaped text up to the first bad escape sequence, along with
+/// the error (if any); without an error, the text is the whole unescaped
+/// string. To find where the error is in the original string, use
+/// [`unescape_spanned`] or [`Unescape::spans`].
+///
+/// ```
+/// # use unescape_zero_copy::{unescape_partial_result, Error};
+/// assert_eq!(
+///     unescape_partial_result(r"ok\tso far\q never"),
+///     ("ok\tso far".to_string(), Some(Error::UnknownSequence('q')))
+/// );
+/// assert_eq!(unescape_partial_result(r"a\nb"), ("a\nb".to_string(), None));
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_partial_result(s: &str) -> (String, Option<Error>) {
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    let mut out = String::with_capacity(s.len());
+    while let Some(fragment) = unescaper.next_fragment() {
+        match fragment.map(StringFragment::text) {
+            Ok(FragmentText::Str(s)) => out.push_str(s),
+            Ok(FragmentText::Char(c)) => out.push(c),
+            Ok(FragmentText::Empty) => (),
+            Err(e) => return (out, Some(e)),
+        }
+    }
+    (out, None)
+}
+
+/// Unescapes the string into a list of its fragments, using the default escape
+/// sequence parser.
+///
+/// Raw fragments borrow from the original string. See
+/// [`Unescape::next_fragment`] for how the string is divided into fragments.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn collect_fragments(s: &str) -> Result<Vec<StringFragment<'_>>, Error> {
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    core::iter::from_fn(|| unescaper.next_fragment()).collect()
+}
+
+/// Unescapes the string into a list of characters, using the default escape
+/// sequence parser.
+///
+/// Enough capacity is reserved up front for the longest possible result (one
+/// character per byte of input), so the list is never reallocated.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_chars(s: &str) -> Result<Vec<char>, Error> {
+    let mut out = Vec::with_capacity(s.len());
+    for ch in UnescapeDefault::new(default_escape_sequence, s) {
+        out.push(ch?);
+    }
+    Ok(out)
+}
+
+/// Unescapes each of the strings with the default escape sequence parser, such
+/// as the fields of a CSV row, sharing one buffer between them.
+///
+/// Each string is unescaped into the shared buffer, then copied out at its
+/// exact length, so the buffer only grows for the longest string rather than
+/// allocating room for each. On the first string which fails to unescape, its
+/// index is returned along with the error.
+///
+/// ```
+/// # use unescape_zero_copy::{unescape_all, Error};
+/// assert_eq!(unescape_all(&["a", r"b\tc", ""]).unwrap(), ["a", "b\tc", ""]);
+/// assert_eq!(
+///     unescape_all(&["ok", r"\x41", r"\q"]),
+///     Err((2, Error::UnknownSequence('q')))
+/// );
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_all(inputs: &[&str]) -> Result<Vec<String>, (usize, Error)> {
+    let mut buf = String::new();
+    let mut out = Vec::with_capacity(inputs.len());
+    for (idx, s) in inputs.iter().enumerate() {
+        buf.clear();
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+        while let Some(fragment) = unescaper.next_fragment() {
+            match fragment.map_err(|e| (idx, e))?.text() {
+                FragmentText::Str(s) => buf.push_str(s),
+                FragmentText::Char(c) => buf.push(c),
+                FragmentText::Empty => (),
+            }
+        }
+        out.push(String::from(buf.as_str()));
+    }
+    Ok(out)
+}
+
+/// Unescapes the string using the default escape sequence parser into bytes,
+/// with `\xNN` escapes producing single bytes rather than characters.
+///
+/// This allows arbitrary binary data, including invalid UTF-8, to be written
+/// as an escaped string; it inverts [`escape_bytes`]. All other escape
+/// sequences, and raw text, produce the UTF-8 encoding of their characters.
+///
+/// ```
+/// # use unescape_zero_copy::unescape_bytes;
+/// assert_eq!(unescape_bytes(r"\xff\xfeé\n").unwrap(), b"\xff\xfe\xc3\xa9\n");
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn unescape_bytes(s: &str) -> Result<Vec<u8>, Error> {
+    unescape_byte_slice(s.as_bytes()).map(Cow::into_owned)
+}
+
+/// Unescapes a byte string as [`unescape_bytes`] does, borrowing it if it
+/// contains no escape sequences.
+///
+/// Raw bytes are kept as-is, so the input doesn't need to be valid UTF-8,
+/// though escape sequences do.
+///
+/// ```
+/// # use unescape_zero_copy::unescape_bstr;
+/// use bstr::ByteSlice;
+/// assert_eq!(unescape_bstr(br"\xff\n".as_bstr()).unwrap(), b"\xff\n".as_bstr());
+/// ```
+#[cfg(feature = "bstr")]
+pub fn unescape_bstr(b: &bstr::BStr) -> Result<Cow<'_, bstr::BStr>, Error> {
+    Ok(match unescape_byte_slice(b)? {
+        Cow::Borrowed(b) => Cow::Borrowed(bstr::BStr::new(b)),
+        Cow::Owned(b) => Cow::Owned(bstr::BString::from(b)),
+    })
+}
+
+/// Unescapes an OS string as [`unescape_bytes`] does, for file paths and other
+/// OS strings which may not be valid UTF-8, borrowing it if it contains no
+/// escape sequences.
+///
+/// This is Unix-only, where OS strings are arbitrary bytes: raw bytes are kept
+/// as-is and `\xNN` escapes produce single bytes, inverting [`escape_os_str`].
+///
+/// ```
+/// # use unescape_zero_copy::unescape_os_str;
+/// use std::ffi::OsStr;
+/// use std::os::unix::ffi::OsStrExt;
+/// let path = unescape_os_str(OsStr::new(r"/tmp/caf\xe9\n")).unwrap();
+/// assert_eq!(path.as_bytes(), b"/tmp/caf\xe9\n");
+/// ```
+#[cfg(all(feature = "std", unix))]
+pub fn unescape_os_str(s: &std::ffi::OsStr) -> Result<Cow<'_, std::ffi::OsStr>, Error> {
+    use std::ffi::{OsStr, OsString};
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    Ok(match unescape_byte_slice(s.as_bytes())? {
+        Cow::Borrowed(b) => Cow::Borrowed(OsStr::from_bytes(b)),
+        Cow::Owned(b) => Cow::Owned(OsString::from_vec(b)),
+    })
+}
+
+/// Unescapes bytes as [`unescape_bytes`] does, only allocating if escape
+/// sequences were found.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn unescape_byte_slice(b: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    let Some(first) = b.iter().position(|&b| b == b'\\') else {
+        return Ok(Cow::Borrowed(b));
+    };
+    let mut out = Vec::with_capacity(b.len());
+    out.extend_from_slice(&b[..first]);
+    let mut read = first + 1;
+    loop {
+        if let Some(hex) = b[read..].strip_prefix(b"x") {
+            // long enough for a digit and any character after it
+            let digits = utf8_prefix(hex, 5);
+            let (byte, _) = match options::hex_prefix(digits, 2) {
+                // the digits stop at invalid UTF-8 rather than the end
+                Err(Error::IncompleteUnicode) if digits.len() < hex.len() => {
+                    let rest = core::str::from_utf8(&hex[digits.len()..]);
+                    return Err(rest.err().map_or(Error::IncompleteUnicode, Error::from));
+                }
+                parsed => parsed?,
+            };
+            // two hex digits always fit in a byte
+            out.push(byte as u8);
+            read += 3;
+        } else {
+            let (ch, consumed) = escape_in_bytes(&b[read..])?;
+            out.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            read += consumed;
+        }
+        match b[read..].iter().position(|&b| b == b'\\') {
+            Some(offset) => {
+                out.extend_from_slice(&b[read..read + offset]);
+                read += offset + 1;
+            }
+            None => {
+                out.extend_from_slice(&b[read..]);
+                return Ok(Cow::Owned(out));
+            }
+        }
+    }
+}
+
+/// Unescapes the string using the default escape sequence parser into UTF-16
+/// code units, as used by Windows and JavaScript APIs.
+///
+/// Characters outside the Basic Multilingual Plane are encoded as surrogate
+/// pairs. The output is encoded directly, without building a [`String`] first.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_utf16(s: &str) -> Result<Vec<u16>, Error> {
+    let mut out = Vec::with_capacity(s.len());
+    for ch in UnescapeDefault::new(default_escape_sequence, s) {
+        out.extend_from_slice(ch?.encode_utf16(&mut [0; 2]));
+    }
+    Ok(out)
+}
+
+/// Unescapes the string using the default escape sequence parser, checking
+/// that every character of the output is allowed.
+///
+/// Both raw and escaped characters are checked, in a single pass over the
+/// string; the first character for which `allowed` returns `false` is
+/// returned as [`Error::DisallowedChar`]. As with [`unescape_default`], this
+/// only allocates if escape sequences were found.
+///
+/// ```
+/// # use unescape_zero_copy::{unescape_validated, Error};
+/// let ident = |c: char| c.is_alphanumeric() || c == '_';
+/// assert_eq!(unescape_validated(r"snake\x5fcase", ident).unwrap(), "snake_case");
+/// assert_eq!(
+///     unescape_validated(r"a\x20b", ident),
+///     Err(Error::DisallowedChar(' '))
+/// );
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_validated<F>(s: &str, allowed: F) -> Result<Cow<'_, str>, Error>
+where
+    F: Fn(char) -> bool,
+{
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    let mut out = Cow::Borrowed("");
+    while let Some(fragment) = unescaper.next_fragment().transpose()? {
+        match fragment.text() {
+            FragmentText::Str(raw) => {
+                if let Some(c) = raw.chars().find(|&c| !allowed(c)) {
+                    return Err(Error::DisallowedChar(c));
+                }
+                out += raw;
+            }
+            FragmentText::Char(c) if allowed(c) => out.to_mut().push(c),
+            FragmentText::Char(c) => return Err(Error::DisallowedChar(c)),
+            FragmentText::Empty => (),
+        }
+    }
+    Ok(out)
+}
+
+/// Unescapes the string using the default escape sequence parser, applying the
+/// function to every character of the output.
+///
+/// Both raw and escaped characters are mapped, in a single pass over the
+/// string. Since mapping may change any character, this always allocates.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_map<F>(s: &str, mut f: F) -> Result<String, Error>
+where
+    F: FnMut(char) -> char,
+{
+    let mut out = String::with_capacity(s.len());
+    for ch in UnescapeDefault::new(default_escape_sequence, s) {
+        out.push(f(ch?));
+    }
+    Ok(out)
+}
+
+/// Unescapes the string with the default escape sequence parser, calling the
+/// callback with each fragment in order, without allocating.
+///
+/// This routes output anywhere the caller likes (e.g. a fixed buffer, hasher,
+/// or counter), with the callback's own error type. Unescaping stops at the
+/// first error from either the string or the callback.
+///
+/// ```
+/// # use unescape_zero_copy::{unescape_each, CallbackError, StringFragment};
+/// let mut escapes = 0;
+/// unescape_each(r"a\tb\nc", |fragment| {
+///     if let StringFragment::Escaped(_) = fragment {
+///         escapes += 1;
+///     }
+///     Ok::<_, ()>(())
+/// })
+/// .unwrap();
+/// assert_eq!(escapes, 2);
+/// ```
+pub fn unescape_each<'a, F, E>(s: &'a str, mut f: F) -> Result<(), CallbackError<E>>
+where
+    F: FnMut(StringFragment<'a>) -> Result<(), E>,
+{
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    while let Some(fragment) = unescaper.next_fragment() {
+        f(fragment.map_err(CallbackError::Unescape)?).map_err(CallbackError::Callback)?;
+    }
+    Ok(())
+}
+
+/// Checks whether two strings are equal after unescaping with the default
+/// escape sequence parser, without allocating.
+///
+/// The strings are unescaped in lockstep, stopping at the first differing
+/// character; an error from either string before then is returned.
+pub fn unescaped_eq(a: &str, b: &str) -> Result<bool, Error> {
+    let mut a = UnescapeDefault::new(default_escape_sequence, a);
+    let mut b = UnescapeDefault::new(default_escape_sequence, b);
+    loop {
+        match (a.next().transpose()?, b.next().transpose()?) {
+            (None, None) => return Ok(true),
+            (a, b) if a != b => return Ok(false),
+            _ => (),
+        }
+    }
+}
+
+/// Checks whether the string unescapes to the expected string with the default
+/// escape sequence parser, without allocating.
+///
+/// This is [`unescaped_eq`] for when only one string is escaped. Unescaping
+/// stops at the first character differing from `expected`; an error before
+/// then is returned.
+///
+/// ```
+/// # use unescape_zero_copy::decodes_to;
+/// assert_eq!(decodes_to(r"tab\there", "tab\there"), Ok(true));
+/// assert_eq!(decodes_to(r"tab\there", r"tab\there"), Ok(false));
+/// ```
+pub fn decodes_to(escaped: &str, expected: &str) -> Result<bool, Error> {
+    let mut expected = expected.chars();
+    for ch in UnescapeDefault::new(default_escape_sequence, escaped) {
+        if expected.next() != Some(ch?) {
+            return Ok(false);
+        }
+    }
+    Ok(expected.next().is_none())
+}
+
+/// Counts the characters the string unescapes to with the default escape
+/// sequence parser, without building the unescaped string.
+///
+/// This is the same as `unescape_default(s)?.chars().count()`, but cheaper;
+/// raw text is counted in place, and each escape sequence counts as one
+/// character. This includes a line continuation (a backslash at the end of a
+/// line), which the default parser keeps as a line break.
+///
+/// ```
+/// # use unescape_zero_copy::decoded_len;
+/// assert_eq!(decoded_len(r"caf\u{e9}\n"), Ok(5));
+/// assert_eq!(decoded_len("café"), Ok(4));
+/// ```
+pub fn decoded_len(s: &str) -> Result<usize, Error> {
+    let mut unescaper = UnescapeDefault::new(default_escape_sequence, s);
+    let mut len = 0;
+    while let Some(fragment) = unescaper.next_fragment().transpose()? {
+        len += match fragment {
+            StringFragment::Raw(s) | StringFragment::Verbatim(s) => s.chars().count(),
+            StringFragment::Empty => 0,
+            StringFragment::Escaped(_)
+            | StringFragment::HighSurrogate(_)
+            | StringFragment::LowSurrogate(_) => 1,
+        };
+    }
+    Ok(len)
+}
+
+/// Unescapes the string with the default escape sequence parser, feeding the
+/// bytes of each character into the hasher rather than building a string.
+///
+/// Each character is written separately, so strings which unescape to the
+/// same text give the same hash however they were escaped; like [`str`]'s
+/// [`Hash`](core::hash::Hash) implementation, a final `0xFF` byte is written
+/// so strings hashed in a row can't collide by moving text between them.
+/// Unescaping stops at the first error, leaving the hasher partially fed.
+///
+/// ```
+/// # use unescape_zero_copy::unescape_hash;
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::Hasher;
+///
+/// let hash = |s| {
+///     let mut hasher = DefaultHasher::new();
+///     unescape_hash(s, &mut hasher).unwrap();
+///     hasher.finish()
+/// };
+/// assert_eq!(hash(r"caf\u{e9}"), hash(r"caf\xe9"));
+/// assert_ne!(hash(r"caf\u{e9}"), hash("cafe"));
+/// ```
+pub fn unescape_hash<H: core::hash::Hasher>(s: &str, hasher: &mut H) -> Result<(), Error> {
+    let mut hash_char = |c: char| hasher.write(c.encode_utf8(&mut [0; 4]).as_bytes());
+    unescape_each(s, |fragment| {
+        match fragment.text() {
+            FragmentText::Str(s) => s.chars().for_each(&mut hash_char),
+            FragmentText::Char(c) => hash_char(c),
+            FragmentText::Empty => (),
+        }
+        Ok::<_, core::convert::Infallible>(())
+    })
+    .map_err(|e| match e {
+        CallbackError::Unescape(e) => e,
+        CallbackError::Callback(never) => match never {},
+    })?;
+    hasher.write_u8(0xFF);
+    Ok(())
+}
+
+/// Borrows the longest valid string from the first `max` bytes of a byte
+/// slice.
+#[inline]
+fn utf8_prefix(bytes: &[u8], max: usize) -> &str {
+    let bytes = &bytes[..bytes.len().min(max)];
+    match core::str::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+    }
+}
+
+/// Parses the escape sequence at the start of the bytes (after the backslash)
+/// with the default escape sequence parser, returning the character and the
+/// number of bytes consumed.
+///
+/// Only a short window of the bytes is checked for UTF-8 up front, so long
+/// inputs aren't validated again for every escape sequence.
+fn escape_in_bytes(bytes: &[u8]) -> Result<(char, usize), Error> {
+    // the default parser reads no further than the closing brace of a
+    // `\u{...}` escape with the most digits, so this is long enough for any
+    // escape sequence even with a multi-byte character cut off at the end
+    const WINDOW: usize = options::MAX_DIGITS + "u{}".len() + 3;
+    let window = utf8_prefix(bytes, WINDOW);
+    if window.is_empty() && !bytes.is_empty() {
+        // the bytes start with invalid UTF-8
+        core::str::from_utf8(bytes)?;
+    }
+    let (ch, rem) = default_escape_sequence(window)?;
+    Ok((ch, window.len() - rem.len()))
+}
+
+/// Unescapes a UTF-8 buffer in place using the default escape sequence parser,
+/// returning the shortened, unescaped string.
+///
+/// Unescaping never lengthens a string, so no allocation is needed. This takes
+/// bytes rather than `&mut str`, as writing arbitrary bytes to a `str` requires
+/// unsafe code; [`Error::InvalidUtf8`] is returned if the buffer isn't valid
+/// UTF-8. On other errors, the contents of the buffer are unspecified (though
+/// still valid UTF-8).
+pub fn unescape_in_place(buf: &mut [u8]) -> Result<&mut str, Error> {
+    core::str::from_utf8(buf)?;
+    let (mut read, mut write) = (0, 0);
+    while let Some(offset) = buf[read..].iter().position(|&b| b == b'\\') {
+        buf.copy_within(read..read + offset, write);
+        write += offset;
+        read += offset + 1;
+        let (ch, consumed) = match escape_in_bytes(&buf[read..]) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                // the bytes left behind by moving text down may split
+                // characters, so overwrite them to keep the buffer valid
+                buf[write..read].fill(b' ');
+                return Err(e);
+            }
+        };
+        read += consumed;
+        write += ch.encode_utf8(&mut buf[write..]).len();
+    }
+    buf.copy_within(read.., write);
+    write += buf.len() - read;
+    Ok(core::str::from_utf8_mut(&mut buf[..write])?)
+}
+
+/// Unescapes a [`Cow`] string using the default escape sequence parser,
+/// keeping its lifetime.
+///
+/// Borrowed strings are unescaped as with [`unescape_default`]. Owned strings
+/// are unescaped in place (see [`unescape_in_place`]), reusing their
+/// allocation rather than copying into a new one.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_cow(input: Cow<'_, str>) -> Result<Cow<'_, str>, Error> {
+    match input {
+        Cow::Borrowed(s) => unescape_default(s),
+        Cow::Owned(s) => {
+            let mut bytes = s.into_bytes();
+            let len = unescape_in_place(&mut bytes)?.len();
+            bytes.truncate(len);
+            let s = String::from_utf8(bytes).map_err(|e| e.utf8_error())?;
+            Ok(Cow::Owned(s))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+    use std::borrow::Cow;
+
+    #[test]
+    fn unescapes_to_bytes() {
+        assert_eq!(
+            unescape_bytes(r"a\x00\x80\u{e9}").unwrap(),
+            b"a\0\x80\xc3\xa9"
+        );
+        assert_eq!(unescape_bytes(r"\x{e9}"), Err(Error::InvalidHexDigit('{')));
+        assert_eq!(unescape_bytes("plain").unwrap(), b"plain");
+        assert_eq!(unescape_bytes(r"\x4"), Err(Error::IncompleteUnicode));
+        assert_eq!(unescape_bytes(r"\x4é"), Err(Error::InvalidHexDigit('é')));
+        assert_eq!(unescape_bytes(r"\xé"), Err(Error::InvalidHexDigit('é')));
+        assert_eq!(unescape_bytes(r"\q"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unescapes_os_strs() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let path = OsStr::from_bytes(b"/tmp/a\xff\\b\n\"c\"");
+        let escaped = escape_os_str(path);
+        assert_eq!(escaped, r#"/tmp/a\xff\x5cb\x0a\x22c\x22"#);
+        assert_eq!(unescape_os_str(OsStr::new(&escaped)).unwrap(), path);
+        let raw = OsStr::from_bytes(b"caf\xe9");
+        assert!(matches!(unescape_os_str(raw).unwrap(), Cow::Borrowed(s) if s == raw));
+        assert_eq!(
+            unescape_os_str(OsStr::from_bytes(b"\xe9\\n")).unwrap(),
+            OsStr::from_bytes(b"\xe9\n")
+        );
+        assert_eq!(
+            unescape_os_str(OsStr::new(r"\q")),
+            Err(Error::UnknownSequence('q'))
+        );
+        assert_eq!(
+            unescape_os_str(OsStr::new(r"\x4é")),
+            Err(Error::InvalidHexDigit('é'))
+        );
+        let err = unescape_os_str(OsStr::from_bytes(b"\\x4\xff")).unwrap_err();
+        assert!(matches!(err, Error::InvalidUtf8(_)));
+        assert!(!err.is_incomplete());
+    }
+
+    #[test]
+    #[cfg(feature = "smallvec")]
+    fn unescapes_into_small_strings() {
+        let s: SmallString = unescape_small(r"a\x41\u{1F600}").unwrap();
+        assert_eq!(&*s, "aA\u{1F600}");
+        assert!(!s.spilled());
+        let s = unescape_small::<4>(r"abc\n").unwrap();
+        assert_eq!(s.as_str(), "abc\n");
+        assert!(!s.spilled());
+        let s = unescape_small::<4>(r"abcd\n").unwrap();
+        assert_eq!(s.as_str(), "abcd\n");
+        assert!(s.spilled());
+        let s = unescape_small::<4>(r"ab\u{1F600}").unwrap();
+        assert_eq!(s.as_str(), "ab\u{1F600}");
+        assert!(s.spilled());
+        assert_eq!(unescape_small::<8>(r"\q"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[test]
+    #[cfg(feature = "bstr")]
+    fn unescapes_byte_strings() {
+        use bstr::ByteSlice;
+        assert!(matches!(
+            unescape_bstr(b"raw \xff".as_bstr()).unwrap(),
+            Cow::Borrowed(b) if b == b"raw \xff"
+        ));
+        assert_eq!(
+            unescape_bstr(b"\xff\\x80\\u{e9}".as_bstr()).unwrap(),
+            b"\xff\x80\xc3\xa9".as_bstr()
+        );
+        assert!(matches!(
+            unescape_bstr(b"a\\\xff".as_bstr()),
+            Err(Error::InvalidUtf8(_))
+        ));
+        assert_eq!(
+            unescape_bstr(b"\\q".as_bstr()),
+            Err(Error::UnknownSequence('q'))
+        );
+    }
+
+    #[test]
+    fn unescapes_to_utf16() {
+        assert_eq!(
+            unescape_utf16(r"aé\u{1F600}\n").unwrap(),
+            [0x61, 0xE9, 0xD83D, 0xDE00, 0x0A]
+        );
+        assert_eq!(unescape_utf16("").unwrap(), []);
+        assert_eq!(unescape_utf16(r"\q"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn utf16_matches_unescape_default(s: String) -> TestResult {
+        match unescape_default(&s) {
+            Ok(out) => TestResult::from_bool(
+                unescape_utf16(&s).unwrap() == out.encode_utf16().collect::<Vec<_>>(),
+            ),
+            Err(_) => TestResult::discard(),
+        }
+    }
+
+    #[test]
+    fn validates_unescaped_characters() {
+        let ascii = |c: char| c.is_ascii_graphic();
+        assert!(matches!(
+            unescape_validated("plain", ascii).unwrap(),
+            Cow::Borrowed("plain")
+        ));
+        assert_eq!(unescape_validated(r"a\x41\\", ascii).unwrap(), "aA\\");
+        assert_eq!(
+            unescape_validated(r"a\nb", ascii),
+            Err(Error::DisallowedChar('\n'))
+        );
+        assert_eq!(
+            unescape_validated("é\\q", ascii),
+            Err(Error::DisallowedChar('é'))
+        );
+        assert_eq!(
+            unescape_validated(r"a\q", ascii),
+            Err(Error::UnknownSequence('q'))
+        );
+    }
+
+    #[test]
+    fn calls_back_with_each_fragment() {
+        let mut fragments = Vec::new();
+        let res = unescape_each(r"ab\ncd\\", |fragment| {
+            fragments.push(fragment);
+            Ok::<_, ()>(())
+        });
+        assert_eq!(res, Ok(()));
+        assert_eq!(
+            fragments,
+            [
+                StringFragment::Raw("ab"),
+                StringFragment::Escaped('\n'),
+                StringFragment::Raw("cd"),
+                StringFragment::Escaped('\\'),
+            ]
+        );
+        assert_eq!(
+            unescape_each(r"a\q", |_| Ok::<_, ()>(())),
+            Err(CallbackError::Unescape(Error::UnknownSequence('q')))
+        );
+        let mut calls = 0;
+        let res = unescape_each(r"a\nb", |_| {
+            calls += 1;
+            Err("full")
+        });
+        assert_eq!(res, Err(CallbackError::Callback("full")));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn measures_escape_sequences() {
+        assert_eq!(escape_len("n"), Ok(2));
+        assert_eq!(escape_len("x41abc"), Ok(4));
+        assert_eq!(escape_len("101"), Ok(4));
+        assert_eq!(escape_len("1019"), Ok(4));
+        assert_eq!(escape_len(r"uD83D\uDE00!"), Ok(12));
+        assert_eq!(escape_len("U0001F600"), Ok(10));
+        assert_eq!(escape_len(""), Err(Error::IncompleteSequence));
+        assert_eq!(escape_len("q"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[test]
+    fn unescapes_cow_strings() {
+        let owned = String::from(r"a\tb\u{1F600}");
+        let ptr = owned.as_ptr();
+        let out = unescape_cow(Cow::Owned(owned)).unwrap();
+        assert_eq!(out, "a\tb\u{1F600}");
+        assert!(matches!(&out, Cow::Owned(s) if s.as_ptr() == ptr));
+        assert!(matches!(
+            unescape_cow(Cow::Borrowed("abc")).unwrap(),
+            Cow::Borrowed("abc")
+        ));
+        assert_eq!(unescape_cow(Cow::Borrowed(r"\n")).unwrap(), "\n");
+        assert_eq!(
+            unescape_cow(Cow::Owned(r"\q".into())),
+            Err(Error::UnknownSequence('q'))
+        );
+    }
+
+    #[test]
+    fn borrow_strings_without_escapes() {
+        assert!(matches!(
+            unescape_default("hello").unwrap(),
+            Cow::Borrowed(_)
         ));
         assert!(matches!(
             unescape_default("longer\nstring").unwrap(),
@@ -380,6 +2183,89 @@ mod test {
         ));
     }
 
+    #[test]
+    fn borrows_exactly_without_escapes() {
+        for s in ["", "a", "hello", "multi\nline", "ünïcödé", "\0raw nul"] {
+            assert_eq!(unescape_borrowed(s), Some(s));
+            assert!(matches!(unescape_default(s).unwrap(), Cow::Borrowed(_)));
+        }
+        for s in [r"\\", r"\\\\", r"a\n", r"\x61", r"\u{61}", "a\\\nb"] {
+            assert_eq!(unescape_borrowed(s), None);
+            assert!(matches!(unescape_default(s).unwrap(), Cow::Owned(_)));
+        }
+        assert_eq!(unescape_borrowed(r"bad\"), None);
+    }
+
+    #[test]
+    fn unescapes_simple_escapes_in_const() {
+        const S: Result<InlineStr<32>, Error> = unescape_simple(r#"a\tb\\c\"ü\n"#);
+        assert_eq!(S.unwrap().as_str(), "a\tb\\c\"ü\n");
+        assert_eq!(&*unescape_simple::<0>("").unwrap(), "");
+        assert_eq!(
+            unescape_simple::<8>(r"\x41"),
+            Err(Error::UnknownSequence('x'))
+        );
+        assert_eq!(
+            unescape_simple::<8>(r"\é"),
+            Err(Error::UnknownSequence('é'))
+        );
+        assert_eq!(unescape_simple::<8>(r"a\"), Err(Error::IncompleteSequence));
+    }
+
+    #[test]
+    #[should_panic = "doesn't fit"]
+    fn simple_unescape_buffer_overflow() {
+        let _ = unescape_simple::<2>("abc");
+    }
+
+    #[test]
+    fn splits_decoded_output() {
+        let split = |s| {
+            UnescapeDefault::new(default_escape_sequence, s)
+                .split_decoded('\n')
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(split("a\nb\\nc\\x0ad\n").unwrap(), ["a", "b", "c", "d", ""]);
+        assert_eq!(split("").unwrap(), [""]);
+        assert_eq!(split(r"a\\n").unwrap(), ["a\\n"]);
+        let mut pieces =
+            UnescapeDefault::new(default_escape_sequence, r"a\nb\q\nc").split_decoded('\n');
+        assert_eq!(pieces.next(), Some(Ok("a".into())));
+        assert_eq!(pieces.next(), Some(Err(Error::UnknownSequence('q'))));
+        assert_eq!(pieces.next(), None);
+    }
+
+    #[test]
+    fn splits_decoded_lines() {
+        let lines = |s| {
+            UnescapeDefault::new(default_escape_sequence, s)
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(lines("a\nb\\nc\\x0ad\n").unwrap(), ["a", "b", "c", "d"]);
+        assert_eq!(
+            lines("a\r\nb\\r\\nc\\r\nd\r").unwrap(),
+            ["a", "b", "c", "d\r"]
+        );
+        assert_eq!(lines("a\n\n").unwrap(), ["a", ""]);
+        assert_eq!(lines("\\n").unwrap(), [""]);
+        assert!(lines("").unwrap().is_empty());
+        let escaped = |s| {
+            UnescapeDefault::new(default_escape_sequence, s)
+                .escaped_lines()
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(
+            escaped("one\nline\\ntwo\r\n\\r\\n").unwrap(),
+            ["one\nline", "two\r\n"]
+        );
+        assert_eq!(escaped("a\n").unwrap(), ["a\n"]);
+        let mut lines = UnescapeDefault::new(default_escape_sequence, "a\n\\q").lines();
+        assert_eq!(lines.next(), Some(Ok("a".into())));
+        assert_eq!(lines.next(), Some(Err(Error::UnknownSequence('q'))));
+        assert_eq!(lines.next(), None);
+    }
+
     #[test]
     fn unescapes_backslashes() {
         assert_eq!(unescape_default(r"\\").unwrap(), "\\");
@@ -392,6 +2278,59 @@ mod test {
         ));
     }
 
+    #[test]
+    fn escaped_backslashes_next_to_escapes() {
+        let cases = [
+            (r"\\n", "\\n"),
+            (r"\\\n", "\\\n"),
+            (r"\\\\n", "\\\\n"),
+            (r"\\\x41", "\\A"),
+            (r"\x41\\", "A\\"),
+            (r"\\x41", "\\x41"),
+            (r"a\\\\\nb", "a\\\\\nb"),
+            (r"\n\\\n\\", "\n\\\n\\"),
+            (r"\\\\\\\\", "\\\\\\\\"),
+            (r"x\\\u{41}\\y", "x\\A\\y"),
+        ];
+        for (escaped, expected) in cases {
+            assert_eq!(unescape_default(escaped).unwrap(), expected, "{escaped}");
+            let chars = UnescapeDefault::new(default_escape_sequence, escaped)
+                .collect::<Result<String, _>>()
+                .unwrap();
+            assert_eq!(chars, expected, "{escaped}");
+            let mut unescaper = UnescapeDefault::new(default_escape_sequence, escaped);
+            let mut fragments = String::new();
+            while let Some(fragment) = unescaper.next_fragment() {
+                match fragment.unwrap() {
+                    StringFragment::Raw(s) => fragments += s,
+                    StringFragment::Escaped(c) => fragments.push(c),
+                    fragment => panic!("unexpected {fragment:?} in {escaped}"),
+                }
+            }
+            assert_eq!(fragments, expected, "{escaped}");
+        }
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn interleaved_backslashes_decode_piecewise(tokens: Vec<u8>) -> bool {
+        const TOKENS: [(&str, &str); 6] = [
+            (r"\\", "\\"),
+            (r"\n", "\n"),
+            (r"\x41", "A"),
+            ("n", "n"),
+            ("x", "x"),
+            (r"\u{42}", "B"),
+        ];
+        let (mut escaped, mut expected) = (String::new(), String::new());
+        for token in tokens {
+            let (from, to) = TOKENS[usize::from(token) % TOKENS.len()];
+            escaped += from;
+            expected += to;
+        }
+        unescape_default(&escaped).unwrap() == expected
+    }
+
     #[test]
     fn unicode_escapes() {
         assert_eq!(unescape_default(r"\u1234").unwrap(), "\u{1234}");
@@ -400,6 +2339,734 @@ mod test {
         assert_eq!(unescape_default(r"\x20").unwrap(), " ");
     }
 
+    #[test]
+    fn unicode_escape_boundaries() {
+        assert_eq!(unescape_default(r"\u{10FFFF}").unwrap(), "\u{10FFFF}");
+        assert_eq!(unescape_default(r"\U0010FFFF").unwrap(), "\u{10FFFF}");
+        assert_eq!(
+            unescape_default(r"\u{110000}"),
+            Err(Error::InvalidUnicode(0x110000))
+        );
+        assert_eq!(
+            unescape_default(r"\U00110000"),
+            Err(Error::InvalidUnicode(0x110000))
+        );
+        assert_eq!(
+            unescape_default(r"\u{FFFFFFFF}"),
+            Err(Error::InvalidUnicode(0xFFFFFFFF))
+        );
+        assert_eq!(
+            unescape_default(r"\UFFFFFFFF"),
+            Err(Error::InvalidUnicode(0xFFFFFFFF))
+        );
+        assert_eq!(
+            unescape_default(r"\u{100000000}"),
+            Err(Error::CodePointOutOfRange)
+        );
+        assert_eq!(unescape_default(r"\u{00000000000041}").unwrap(), "A");
+        let huge = format!(r"\u{{{}", "f".repeat(1 << 20));
+        assert_eq!(unescape_default(&huge), Err(Error::CodePointOutOfRange));
+        assert_eq!(
+            unescape_default(r"\u{0000000000000000000000000000000000041}"),
+            Err(Error::CodePointOutOfRange)
+        );
+        assert_eq!(unescape_default(r"\u{1F60"), Err(Error::IncompleteUnicode));
+        // `\U` takes exactly eight digits, so can't overflow
+        assert_eq!(unescape_default(r"\U000000410").unwrap(), "A0");
+    }
+
+    #[test]
+    fn fixed_width_escapes_need_every_digit() {
+        assert_eq!(unescape_default(r"\x4"), Err(Error::IncompleteUnicode));
+        assert_eq!(unescape_default(r"\x4g"), Err(Error::InvalidHexDigit('g')));
+        assert_eq!(
+            unescape_default(r"\x4\n"),
+            Err(Error::InvalidHexDigit('\\'))
+        );
+        assert_eq!(
+            unescape_default(r"\u123\n"),
+            Err(Error::InvalidHexDigit('\\'))
+        );
+        assert_eq!(
+            unescape_default(r"\u123é"),
+            Err(Error::InvalidHexDigit('é'))
+        );
+        assert_eq!(
+            unescape_default(r"\u+004"),
+            Err(Error::InvalidHexDigit('+'))
+        );
+        assert_eq!(unescape_default(r"\xé"), Err(Error::InvalidHexDigit('é')));
+        assert!(!Error::InvalidHexDigit('g').is_incomplete());
+        assert_eq!(unescape_default(r"\u12"), Err(Error::IncompleteUnicode));
+        assert_eq!(unescape_default(r"\x4a\n").unwrap(), "J\n");
+    }
+
+    #[test]
+    fn octal_escapes() {
+        assert_eq!(unescape_default(r"\101\0\7").unwrap(), "A\0\x07");
+        assert_eq!(unescape_default(r"\1018").unwrap(), "A8");
+        assert_eq!(unescape_default(r"\8"), Err(Error::InvalidOctalDigit('8')));
+        assert_eq!(unescape_default(r"a\9"), Err(Error::InvalidOctalDigit('9')));
+        assert_eq!(
+            Error::InvalidOctalDigit('8').to_string(),
+            "`8` is not a valid octal digit"
+        );
+    }
+
+    #[test]
+    fn spans_errors() {
+        let err = unescape_spanned(r"ab\qcd").unwrap_err();
+        assert_eq!(err.error, Error::UnknownSequence('q'));
+        assert_eq!(err.span, 2..4);
+        assert_eq!(
+            err.to_string(),
+            "unknown escape sequence starting with `q` at bytes 2..4"
+        );
+        assert_eq!(unescape_spanned(r"é\n\é").unwrap_err().span, 4..7);
+        assert_eq!(unescape_spanned(r"\n\").unwrap_err().span, 2..3);
+        assert_eq!(unescape_spanned(r"a\tb").unwrap(), "a\tb");
+    }
+
+    #[test]
+    #[cfg(feature = "miette")]
+    fn spanned_errors_are_diagnostics() {
+        use miette::Diagnostic;
+        let err = unescape_spanned(r"ab\qcd").unwrap_err();
+        let labels: Vec<_> = err.labels().unwrap().collect();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].offset(), 2);
+        assert_eq!(labels[0].len(), 2);
+        assert_eq!(
+            labels[0].label(),
+            Some("unknown escape sequence starting with `q`")
+        );
+    }
+
+    #[test]
+    fn unescapes_first_sequence_only() {
+        let (out, rem) = unescape_first(r"ab\ncd\tef").unwrap();
+        assert_eq!(out, "ab\n");
+        assert_eq!(rem, r"cd\tef");
+        let (out, rem) = unescape_first(r"\x41\x42").unwrap();
+        assert_eq!(out, "A");
+        assert_eq!(rem, r"\x42");
+        let (out, rem) = unescape_first("plain").unwrap();
+        assert!(matches!(out, Cow::Borrowed("plain")));
+        assert_eq!(rem, "");
+        assert!(matches!(
+            unescape_first(r"ab\"),
+            Err(Error::IncompleteSequence)
+        ));
+    }
+
+    #[test]
+    fn unescapes_n_sequences() {
+        let s = r"ab\ncd\tef\x41";
+        let (out, rem) = unescape_n(s, 0).unwrap();
+        assert_eq!(out, "");
+        assert_eq!(rem, s);
+        let (out, rem) = unescape_n(s, 2).unwrap();
+        assert_eq!(out, "ab\ncd\t");
+        assert_eq!(rem, r"ef\x41");
+        let (out, rem) = unescape_n(s, 3).unwrap();
+        assert_eq!(out, "ab\ncd\tefA");
+        assert_eq!(rem, "");
+        let (out, rem) = unescape_n(r"a\nb", 5).unwrap();
+        assert_eq!(out, "a\nb");
+        assert_eq!(rem, "");
+        // errors past the nth escape sequence are left in the remainder
+        let (out, rem) = unescape_n(r"\n\q", 1).unwrap();
+        assert_eq!(out, "\n");
+        assert_eq!(rem, r"\q");
+        assert_eq!(unescape_n(r"\n\q", 2), Err(Error::UnknownSequence('q')));
+        // batches put back together give the whole string
+        let mut rem = s;
+        let mut out = String::new();
+        while !rem.is_empty() {
+            let (batch, next) = unescape_n(rem, 2).unwrap();
+            out.push_str(&batch);
+            rem = next;
+        }
+        assert_eq!(out, unescape_default(s).unwrap());
+    }
+
+    #[test]
+    fn incomplete_errors() {
+        assert!(Error::IncompleteSequence.is_incomplete());
+        assert!(Error::IncompleteUnicode.is_incomplete());
+        assert!(!Error::UnknownSequence('q').is_incomplete());
+        assert!(!Error::InvalidUnicode(0xD800).is_incomplete());
+        assert!(unescape_default(r"\u{1F6").unwrap_err().is_incomplete());
+        assert!(!unescape_default(r"\u{D800}").unwrap_err().is_incomplete());
+    }
+
+    #[test]
+    fn partial_unescaping_leaves_incomplete_escapes() {
+        assert_eq!(unescape_partial(r"ab\n").unwrap(), ("ab\n".into(), ""));
+        assert_eq!(unescape_partial(r"ab\n\").unwrap(), ("ab\n".into(), r"\"));
+        assert_eq!(unescape_partial(r"ab\u12").unwrap(), ("ab".into(), r"\u12"));
+        assert_eq!(unescape_partial(r"\u{1F6").unwrap(), ("".into(), r"\u{1F6"));
+        assert_eq!(unescape_partial(r"\x4").unwrap(), ("".into(), r"\x4"));
+        assert!(matches!(
+            unescape_partial(r"ab\q"),
+            Err(Error::UnknownSequence('q'))
+        ));
+        assert_eq!(
+            unescape_partial(r"ab\u12zz"),
+            Err(Error::InvalidHexDigit('z'))
+        );
+        assert_eq!(
+            unescape_partial(r"ab\x4\n"),
+            Err(Error::InvalidHexDigit('\\'))
+        );
+    }
+
+    #[test]
+    fn collects_fragments() {
+        assert_eq!(
+            collect_fragments(r"ab\ncd\\").unwrap(),
+            [
+                StringFragment::Raw("ab"),
+                StringFragment::Escaped('\n'),
+                StringFragment::Raw("cd"),
+                StringFragment::Escaped('\\'),
+            ]
+        );
+        assert_eq!(collect_fragments("").unwrap(), []);
+        assert_eq!(collect_fragments(r"ab\q"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[test]
+    fn unescapes_in_place() {
+        let mut buf = *br"a\nb\u{e9}\\c\U0001F600";
+        assert_eq!(unescape_in_place(&mut buf).unwrap(), "a\nbé\\c\u{1F600}");
+        let mut buf = *b"no escapes";
+        assert_eq!(unescape_in_place(&mut buf).unwrap(), "no escapes");
+        let mut buf = format!(r"\u{{{}41}}x", "0".repeat(20)).into_bytes();
+        assert_eq!(unescape_in_place(&mut buf).unwrap(), "Ax");
+        // errors for long digit runs are the same as when unescaping a copy
+        for s in [
+            format!(r"\u{{{}41}}", "0".repeat(30)),
+            format!(r"\u{{{}41}}", "0".repeat(31)),
+            format!(r"\u{{{}", "0".repeat(100)),
+            format!(r"\u{{{}é}}", "0".repeat(31)),
+            format!(r"\x4g{}", r"\u{0".repeat(100)),
+        ] {
+            let mut buf = s.clone().into_bytes();
+            assert_eq!(
+                unescape_in_place(&mut buf).map(|s| s.to_string()),
+                unescape_default(&s).map(Cow::into_owned),
+                "{s:?}"
+            );
+        }
+        let mut buf = *br"ab\";
+        assert_eq!(unescape_in_place(&mut buf), Err(Error::IncompleteSequence));
+        let mut buf = *b"\xff\n";
+        assert!(matches!(
+            unescape_in_place(&mut buf),
+            Err(Error::InvalidUtf8(_))
+        ));
+        let mut buf = *b"\\n\xc3\xa9\xc3\xa9\xc3\xa9\\q";
+        assert_eq!(
+            unescape_in_place(&mut buf),
+            Err(Error::UnknownSequence('q'))
+        );
+        assert!(core::str::from_utf8(&buf).is_ok());
+    }
+
+    #[test]
+    fn coalesces_runs_of_text() {
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"ab\\cd\n");
+        assert_eq!(unescaper.next_coalesced().unwrap().unwrap(), "ab\\cd\n");
+        assert!(unescaper.next_coalesced().is_none());
+
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, "plain");
+        assert!(matches!(
+            unescaper.next_coalesced(),
+            Some(Ok(Cow::Borrowed("plain")))
+        ));
+
+        // `\z` separates runs, as in Lua
+        let parser = |s: &'static str| match s.strip_prefix('z') {
+            Some(rem) => Ok((None, rem)),
+            None => default_escape_sequence(s).map(|(c, rem)| (Some(c), rem)),
+        };
+        let mut unescaper = Unescape::new(parser, r"\za\tb\z\zc\zd\q");
+        assert_eq!(unescaper.next_coalesced().unwrap().unwrap(), "a\tb");
+        assert!(matches!(
+            unescaper.next_coalesced(),
+            Some(Ok(Cow::Borrowed("c")))
+        ));
+        assert_eq!(
+            unescaper.next_coalesced(),
+            Some(Err(Error::UnknownSequence('q')))
+        );
+        assert!(unescaper.next_coalesced().is_none());
+    }
+
+    #[test]
+    fn unescapes_to_chars() {
+        assert_eq!(
+            unescape_chars(r"é\n\u{1F600}a").unwrap(),
+            ['é', '\n', '\u{1F600}', 'a']
+        );
+        assert_eq!(unescape_chars("").unwrap(), []);
+        assert_eq!(unescape_chars(r"a\q"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[test]
+    fn maps_unescaped_characters() {
+        assert_eq!(
+            unescape_map(r"Foo\x42ar\n", |c| c.to_ascii_lowercase()).unwrap(),
+            "foobar\n"
+        );
+        assert_eq!(
+            unescape_map(r"ab", |c| c.to_ascii_uppercase()).unwrap(),
+            "AB"
+        );
+        assert_eq!(
+            unescape_map(r"a\q", |c| c),
+            Err(Error::UnknownSequence('q'))
+        );
+    }
+
+    #[test]
+    fn fragments_as_bytes() {
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"ab\né");
+        assert_eq!(
+            unescaper.next_fragment_bytes(),
+            Some(Ok(StringFragmentBytes::Raw(b"ab")))
+        );
+        assert_eq!(
+            unescaper.next_fragment_bytes(),
+            Some(Ok(StringFragmentBytes::Escaped('\n')))
+        );
+        assert_eq!(
+            unescaper.next_fragment_bytes(),
+            Some(Ok(StringFragmentBytes::Raw("é".as_bytes())))
+        );
+        assert_eq!(unescaper.next_fragment_bytes(), None);
+    }
+
+    #[test]
+    fn compares_unescaped_strings() {
+        assert_eq!(unescaped_eq(r"a\x62c", r"\u0061b\143"), Ok(true));
+        assert_eq!(unescaped_eq("a\nb", r"a\nb"), Ok(true));
+        assert_eq!(unescaped_eq(r"ab", r"a\x62c"), Ok(false));
+        assert_eq!(unescaped_eq(r"ab", r"ac\q"), Ok(false));
+        assert_eq!(
+            unescaped_eq(r"a\q", r"ab"),
+            Err(Error::UnknownSequence('q'))
+        );
+    }
+
+    #[test]
+    fn checks_decoded_strings() {
+        assert_eq!(decodes_to(r"a\x62\u{63}", "abc"), Ok(true));
+        assert_eq!(decodes_to("", ""), Ok(true));
+        assert_eq!(decodes_to(r"ab\n", "ab"), Ok(false));
+        assert_eq!(decodes_to("ab", "abc"), Ok(false));
+        assert_eq!(decodes_to(r"ax\q", "ab"), Ok(false));
+        assert_eq!(decodes_to(r"a\q", "ab"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[test]
+    fn counts_decoded_chars() {
+        for s in [
+            "",
+            "plain",
+            "caf\u{e9}",
+            r"\n\t\\",
+            r"caf\u{e9} \u{1F600}\x41",
+            r"\uD83D\uDE00",
+            "line\\\n    continued",
+            r"\101\0",
+        ] {
+            assert_eq!(
+                decoded_len(s),
+                Ok(unescape_default(s).unwrap().chars().count()),
+                "{s:?}"
+            );
+        }
+        assert_eq!(decoded_len(r"\u{1F600}"), Ok(1));
+        // the line break of a line continuation is kept
+        assert_eq!(decoded_len("a\\\nb"), Ok(3));
+        assert_eq!(decoded_len(r"ab\q"), Err(Error::UnknownSequence('q')));
+    }
+
+    #[test]
+    fn hashes_unescaped_text() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+        let hash = |s| {
+            let mut hasher = DefaultHasher::new();
+            unescape_hash(s, &mut hasher).map(|()| hasher.finish())
+        };
+        let same = [
+            "caf\u{e9}\n",
+            r"caf\u{e9}\n",
+            r"\x63af\xe9\x0a",
+            r"\143\141\146\351\012",
+        ];
+        for s in same {
+            assert_eq!(hash(s), hash(same[0]), "{s:?}");
+        }
+        assert_ne!(hash("ab"), hash(r"a\x62c"));
+        assert_eq!(hash(r"ab\q"), Err(Error::UnknownSequence('q')));
+        // the hash is a function of the unescaped characters
+        let mut a = DefaultHasher::new();
+        unescape_hash(r"\x41\u{1F600}", &mut a).unwrap();
+        let mut b = DefaultHasher::new();
+        for c in "A\u{1F600}".chars() {
+            b.write(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+        b.write_u8(0xFF);
+        assert_eq!(a.finish(), b.finish());
+    }
+
+    #[test]
+    fn converts_errors_into_io() {
+        use std::io::ErrorKind;
+        fn read(s: &str) -> std::io::Result<String> {
+            Ok(unescape_default(s)?.into_owned())
+        }
+        assert_eq!(read(r"\x41").unwrap(), "A");
+        let err = read(r"\q").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), Error::UnknownSequence('q').to_string());
+        assert_eq!(
+            err.get_ref().and_then(|e| e.downcast_ref()),
+            Some(&Error::UnknownSequence('q'))
+        );
+    }
+
+    #[test]
+    fn adapts_errors_into_io() {
+        use std::io::ErrorKind;
+        let chars = UnescapeDefault::new(default_escape_sequence, r"a\x41\q").into_io();
+        let items: Vec<_> = chars.collect();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().unwrap(), &'a');
+        assert_eq!(items[1].as_ref().unwrap(), &'A');
+        let err = items[2].as_ref().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.get_ref().and_then(|e| e.downcast_ref()),
+            Some(&Error::UnknownSequence('q'))
+        );
+        let s: std::io::Result<String> =
+            UnescapeDefault::new(default_escape_sequence, r"\u{1F600}")
+                .into_io()
+                .collect();
+        assert_eq!(s.unwrap(), "\u{1F600}");
+    }
+
+    #[test]
+    fn keeps_text_before_errors() {
+        assert_eq!(unescape_partial_result(""), (String::new(), None));
+        assert_eq!(
+            unescape_partial_result(r"a\x41\u{1F600}"),
+            ("aA\u{1F600}".to_string(), None)
+        );
+        assert_eq!(
+            unescape_partial_result(r"a\x41\q\n"),
+            ("aA".to_string(), Some(Error::UnknownSequence('q')))
+        );
+        assert_eq!(
+            unescape_partial_result(r"\8"),
+            (String::new(), Some(Error::InvalidOctalDigit('8')))
+        );
+        assert_eq!(
+            unescape_partial_result(r"ab\u12"),
+            ("ab".to_string(), Some(Error::IncompleteUnicode))
+        );
+        assert_eq!(
+            unescape_partial_result("ends\\"),
+            ("ends".to_string(), Some(Error::IncompleteSequence))
+        );
+    }
+
+    #[test]
+    fn converts_from_str() {
+        fn collect<'a>(s: impl Into<UnescapeDefault<'a>>) -> Result<String, Error> {
+            s.into().collect()
+        }
+        assert_eq!(collect(r"a\x41\n"), Ok("aA\n".to_string()));
+        assert_eq!(
+            UnescapeDefault::from(r"\q").next(),
+            Some(Err(Error::UnknownSequence('q')))
+        );
+    }
+
+    #[test]
+    fn reports_last_escape_text() {
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"\n\u{1F600}b\\\q");
+        assert_eq!(unescaper.last_escape_text(), None);
+        assert_eq!(unescaper.next(), Some(Ok('\n')));
+        assert_eq!(unescaper.last_escape_text(), Some(r"\n"));
+        assert_eq!(unescaper.peek(), Some(&Ok('\u{1F600}')));
+        assert_eq!(unescaper.last_escape_text(), Some(r"\n"));
+        assert_eq!(unescaper.next(), Some(Ok('\u{1F600}')));
+        assert_eq!(unescaper.last_escape_text(), Some(r"\u{1F600}"));
+        assert_eq!(unescaper.next(), Some(Ok('b')));
+        assert_eq!(unescaper.last_escape_text(), None);
+        assert_eq!(unescaper.next(), Some(Ok('\\')));
+        assert_eq!(unescaper.last_escape_text(), Some(r"\\"));
+        assert_eq!(unescaper.next(), Some(Err(Error::UnknownSequence('q'))));
+        assert_eq!(unescaper.last_escape_text(), None);
+
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"\tab");
+        unescaper.next();
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("ab")))
+        );
+        assert_eq!(unescaper.last_escape_text(), None);
+    }
+
+    #[test]
+    fn unescapes_many_strings() {
+        let row = [r"name", r"tab\there", "", r"\u{1F600}\\", r"\x41\102"];
+        let unescaped = unescape_all(&row).unwrap();
+        assert_eq!(unescaped, ["name", "tab\there", "", "\u{1F600}\\", "AB"]);
+        for (out, s) in unescaped.iter().zip(row) {
+            assert_eq!(*out, unescape_default(s).unwrap());
+            assert_eq!(out.capacity(), out.len());
+        }
+        assert_eq!(unescape_all(&[]), Ok(vec![]));
+        assert_eq!(
+            unescape_all(&["ok", r"\8", r"\q"]),
+            Err((1, Error::InvalidOctalDigit('8')))
+        );
+    }
+
+    #[test]
+    fn latin1_range_forms_agree() {
+        for num in 0..=0xFFu32 {
+            let expected = char::from_u32(num).unwrap();
+            let forms = [
+                format!(r"\x{num:02x}"),
+                format!(r"\x{num:02X}"),
+                format!(r"\u{num:04x}"),
+                format!(r"\u{num:04X}"),
+                format!(r"\u{{{num:x}}}"),
+                format!(r"\U{num:08x}"),
+                format!(r"\{num:03o}"),
+            ];
+            for form in forms {
+                assert_eq!(
+                    unescape_default(&form).unwrap(),
+                    expected.to_string(),
+                    "{form}"
+                );
+                let mut buf = form.clone().into_bytes();
+                assert_eq!(
+                    unescape_in_place(&mut buf).unwrap(),
+                    expected.encode_utf8(&mut [0; 4]),
+                    "{form}"
+                );
+            }
+        }
+        assert_eq!(unescape_default(r"\u00E9").unwrap(), "\u{E9}");
+        assert_eq!(unescape_default(r"\xE9").unwrap(), "\u{E9}");
+    }
+
+    #[test]
+    fn tracks_position() {
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"ab\ncd\q");
+        assert_eq!(unescaper.position(), 0);
+        unescaper.next();
+        assert_eq!(unescaper.position(), 1);
+        assert!(matches!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("b")))
+        ));
+        assert_eq!(unescaper.position(), 2);
+        assert_eq!(unescaper.next().unwrap().unwrap(), '\n');
+        assert_eq!(unescaper.position(), 4);
+        assert!(matches!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("cd")))
+        ));
+        assert_eq!(unescaper.position(), 6);
+        assert!(unescaper.next().unwrap().is_err());
+        assert_eq!(unescaper.position(), 6);
+        assert!(unescaper.next().is_none());
+
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"h\u00e9llo");
+        assert_eq!(unescaper.by_ref().count(), 5);
+        assert_eq!(unescaper.position(), 10);
+    }
+
+    #[test]
+    fn spans_characters() {
+        let spans = |s| {
+            UnescapeDefault::new(default_escape_sequence, s)
+                .spans()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            spans(r"é\n\u{1F600}b"),
+            [
+                Ok((0..2, 'é')),
+                Ok((2..4, '\n')),
+                Ok((4..13, '\u{1F600}')),
+                Ok((13..14, 'b')),
+            ]
+        );
+        assert_eq!(spans(""), []);
+        assert_eq!(
+            spans(r"a\qb"),
+            [
+                Ok((0..1, 'a')),
+                Err(SpannedError {
+                    error: Error::UnknownSequence('q'),
+                    span: 1..3,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn continues_from_non_suffix_remainder() {
+        let tail = |_: &str| Ok::<_, Error>(('X', "a much longer static tail"));
+        assert_eq!(
+            unescape(tail, r"a\q").unwrap(),
+            "aXa much longer static tail"
+        );
+        let spans = Unescape::new(tail, r"a\qb")
+            .spans()
+            .take(3)
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [Ok((0..1, 'a')), Ok((1..4, 'X')), Ok((4..5, 'a'))]);
+        let mut unescaper = Unescape::new(tail, r"a\q");
+        assert_eq!(unescaper.next(), Some(Ok('a')));
+        assert_eq!(unescaper.peek(), Some(&Ok('X')));
+        assert_eq!(unescaper.remainder(), Some(r"\q"));
+        assert_eq!(
+            unescaper.next_fragment_detailed(),
+            Some(Ok((StringFragment::Escaped('X'), Some(EscapeForm::Named))))
+        );
+        assert_eq!(unescaper.last_escape_text(), None);
+        assert_eq!(unescaper.remainder(), Some("a much longer static tail"));
+        assert_eq!(unescaper.position(), 3);
+        // equal text from elsewhere isn't a suffix
+        let s = String::from("xyz");
+        assert!(is_suffix(&s[1..], &s) && is_suffix("", &s));
+        assert!(!is_suffix("yz", &s) && !is_suffix(&s, &s[1..]));
+    }
+
+    #[test]
+    fn peek_caches_next_char() {
+        let mut calls = 0;
+        let mut unescaper = Unescape::new(
+            |s| {
+                calls += 1;
+                default_escape_sequence(s)
+            },
+            r"a\nb\q",
+        );
+        assert_eq!(unescaper.peek(), Some(&Ok('a')));
+        assert_eq!(unescaper.peek(), Some(&Ok('a')));
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("a")))
+        );
+        assert_eq!(unescaper.peek(), Some(&Ok('\n')));
+        assert_eq!(unescaper.remainder(), Some(r"\nb\q"));
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Escaped('\n')))
+        );
+        assert_eq!(unescaper.peek(), Some(&Ok('b')));
+        assert_eq!(unescaper.next(), Some(Ok('b')));
+        assert_eq!(unescaper.peek(), Some(&Err(Error::UnknownSequence('q'))));
+        assert_eq!(unescaper.position(), 4);
+        assert_eq!(unescaper.next(), Some(Err(Error::UnknownSequence('q'))));
+        assert_eq!(unescaper.peek(), None);
+        assert_eq!(unescaper.next(), None);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn mixes_chars_and_fragments() {
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"ab\ncd");
+        assert_eq!(unescaper.next(), Some(Ok('a')));
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("b")))
+        );
+        assert_eq!(unescaper.position(), 2);
+        assert_eq!(unescaper.next(), Some(Ok('\n')));
+        assert_eq!(unescaper.next(), Some(Ok('c')));
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("d")))
+        );
+        assert_eq!(unescaper.next_fragment(), None);
+        assert_eq!(unescaper.remainder(), None);
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"a\n");
+        assert_eq!(unescaper.next(), Some(Ok('a')));
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Escaped('\n')))
+        );
+    }
+
+    #[test]
+    fn detects_trivial_strings() {
+        assert!(UnescapeDefault::new(default_escape_sequence, "plain").is_trivial());
+        assert!(UnescapeDefault::new(default_escape_sequence, "").is_trivial());
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"a\nb");
+        assert!(!unescaper.is_trivial());
+        unescaper.next();
+        assert!(!unescaper.is_trivial());
+        unescaper.next();
+        assert!(unescaper.is_trivial());
+        assert!(!UnescapeDefault::new(default_escape_sequence, "a\\").is_trivial());
+    }
+
+    #[test]
+    fn counts_remaining_escapes() {
+        let mut unescaper = UnescapeDefault::new(default_escape_sequence, r"ab\n\\\x41c\");
+        assert_eq!(unescaper.count_remaining_escapes(), 4);
+        unescaper.next();
+        assert_eq!(unescaper.count_remaining_escapes(), 4);
+        unescaper.nth(1);
+        assert_eq!(unescaper.count_remaining_escapes(), 3);
+        assert_eq!(unescaper.count_remaining_escapes(), 3);
+        unescaper.nth(2);
+        assert_eq!(unescaper.count_remaining_escapes(), 1);
+        let unescaper = UnescapeDefault::new(default_escape_sequence, "plain");
+        assert_eq!(unescaper.count_remaining_escapes(), 0);
+    }
+
+    #[test]
+    fn reports_escape_forms() {
+        let mut unescaper = UnescapeDefault::new(
+            default_escape_sequence,
+            concat!(r"a\n\x41\u0042\U00000043\u{44}\105\\", "\\\n"),
+        );
+        let mut forms = Vec::new();
+        while let Some(fragment) = unescaper.next_fragment_detailed() {
+            forms.push(fragment.unwrap().1);
+        }
+        assert_eq!(
+            forms,
+            [
+                None,
+                Some(EscapeForm::Named),
+                Some(EscapeForm::HexShort),
+                Some(EscapeForm::HexUnicode4),
+                Some(EscapeForm::HexUnicode8),
+                Some(EscapeForm::BracedUnicode),
+                Some(EscapeForm::Octal),
+                Some(EscapeForm::SelfEscape),
+                Some(EscapeForm::LineContinuation),
+            ]
+        );
+    }
+
     #[quickcheck]
     #[cfg_attr(miri, ignore = "slow to run under Miri")]
     fn inverts_escape_default(s: String) -> TestResult {
@@ -414,6 +3081,38 @@ mod test {
             Err(e) => TestResult::error(e.to_string()),
         }
     }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn decoded_len_matches_unescape_default(s: String) -> TestResult {
+        let escaped: String = s.escape_default().collect();
+        match decoded_len(&escaped) {
+            Ok(len) => TestResult::from_bool(len == s.chars().count()),
+            Err(e) => TestResult::error(e.to_string()),
+        }
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn in_place_matches_unescape_default(s: String) -> TestResult {
+        let escaped: String = s.escape_default().collect();
+        let mut buf = escaped.clone().into_bytes();
+        match unescape_in_place(&mut buf) {
+            Ok(unescaped) => {
+                TestResult::from_bool(*unescaped == unescape_default(&escaped).unwrap())
+            }
+            Err(e) => TestResult::error(e.to_string()),
+        }
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn in_place_error_keeps_utf8(s: String, escapes: u8) -> bool {
+        // raw text is moved down over each escape sequence before the error
+        let text = s.replace('\\', "");
+        let mut buf = format!("{}{text}\\q", "\\n".repeat(escapes.into())).into_bytes();
+        unescape_in_place(&mut buf).is_err() && core::str::from_utf8(&buf).is_ok()
+    }
 }
 #[cfg(all(test, not(feature = "std")))]
 compile_error!("Tests currently require `std` feature");
@@ -0,0 +1,139 @@
+//! Reporting escape sequences which could be written more simply.
+
+use core::ops::Range;
+
+use crate::escape::needs_escape;
+use crate::{default_escape_sequence, escape_char, SpannedError, Unescape};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// The ways an escape sequence can be written more simply, as reported by
+/// [`lint`].
+///
+/// More kinds of lint may be added in the future, so matches on it need a
+/// wildcard arm.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// A character which needs no escaping, such as `\x61` for `a`.
+    Unnecessary,
+    /// A numeric escape for a character with a named escape, such as `\x0A`
+    /// for `\n`.
+    NamedAvailable,
+    /// A braced escape with leading zeros, such as `\u{000e9}` for `\u{e9}`.
+    LeadingZeros,
+}
+
+/// A non-canonical escape sequence found by [`lint`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lint {
+    /// How the escape sequence could be simplified.
+    pub kind: LintKind,
+    /// The byte range of the escape sequence in the original string,
+    /// including its backslash.
+    pub span: Range<usize>,
+}
+
+/// Reports the escape sequences in the string which could be written more
+/// simply, for the default unescaper.
+///
+/// Decoding is unaffected: every sequence reported still unescapes to the same
+/// character. Line continuations are never reported. If the string doesn't
+/// unescape, the error is returned with its span instead.
+///
+/// ```
+/// # use unescape_zero_copy::{lint, Lint, LintKind};
+/// assert_eq!(
+///     lint(r"\x0A\u{000061}\n").unwrap(),
+///     [
+///         Lint { kind: LintKind::NamedAvailable, span: 0..4 },
+///         Lint { kind: LintKind::Unnecessary, span: 4..14 },
+///     ]
+/// );
+/// ```
+pub fn lint(s: &str) -> Result<Vec<Lint>, SpannedError> {
+    let mut lints = Vec::new();
+    for item in Unescape::new(default_escape_sequence, s).spans() {
+        let (span, c) = item?;
+        let Some(sequence) = s[span.clone()].strip_prefix('\\') else {
+            continue;
+        };
+        let kind = if sequence.starts_with(['\r', '\n']) {
+            continue;
+        } else if c.is_ascii() && !needs_escape(c) {
+            LintKind::Unnecessary
+        } else if sequence.len() > 1
+            && escape_char(c).len() == 2
+            && !octal_ambiguous(c, &s[span.end..])
+        {
+            LintKind::NamedAvailable
+        } else if braced_digits(sequence).is_some_and(|d| d.len() > 1 && d.starts_with('0')) {
+            LintKind::LeadingZeros
+        } else {
+            continue;
+        };
+        lints.push(Lint { kind, span });
+    }
+    Ok(lints)
+}
+
+/// Whether the named escape of the character would run into a digit after it,
+/// as `\0` followed by a digit is read as an octal escape.
+#[inline]
+fn octal_ambiguous(c: char, after: &str) -> bool {
+    c == '\0' && after.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Returns the digits of a braced escape sequence, given the text after its
+/// backslash.
+fn braced_digits(sequence: &str) -> Option<&str> {
+    let braced = sequence.get(1..)?.strip_prefix('{')?;
+    braced.split_once('}').map(|(digits, _)| digits)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::{escape, Error};
+    use quickcheck_macros::quickcheck;
+
+    fn kinds(s: &str) -> Vec<LintKind> {
+        lint(s).unwrap().into_iter().map(|lint| lint.kind).collect()
+    }
+
+    #[test]
+    fn reports_non_canonical_escapes() {
+        assert_eq!(kinds(r"plain \n\t\\\x01\u{1F600}"), []);
+        assert_eq!(
+            kinds(r"\x0a\012\u000A\U0000000a\u{a}"),
+            [LintKind::NamedAvailable; 5]
+        );
+        assert_eq!(kinds(r"\x61A\/\141\u{61}"), [LintKind::Unnecessary; 4]);
+        assert_eq!(
+            kinds(r"\u{01F600}\u{07f}\u{00}"),
+            [
+                LintKind::LeadingZeros,
+                LintKind::LeadingZeros,
+                LintKind::NamedAvailable
+            ]
+        );
+        assert_eq!(kinds(r"\x00\u{0}1\0"), [LintKind::NamedAvailable]);
+        assert_eq!(kinds("a\\\nb"), []);
+    }
+
+    #[test]
+    fn reports_spans() {
+        let lints = lint(r"ab\x41\u{00e9}").unwrap();
+        assert_eq!(lints[0].span, 2..6);
+        assert_eq!(lints[1].span, 6..14);
+        let err = lint(r"ok\x41\q").unwrap_err();
+        assert_eq!(err.error, Error::UnknownSequence('q'));
+        assert_eq!(err.span, 6..8);
+    }
+
+    #[quickcheck]
+    #[cfg_attr(miri, ignore = "slow to run under Miri")]
+    fn escape_is_canonical(s: String) -> bool {
+        lint(&escape(&s)).unwrap().is_empty()
+    }
+}
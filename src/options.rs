@@ -0,0 +1,2354 @@
+//! Configurable variations on the default escape sequence parser.
+
+use core::fmt;
+use core::num::IntErrorKind;
+
+use crate::{decode_char, join_surrogates, simple_escape, Error, StringFragment, Unescape};
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::{unescape, Cow, FragmentText};
+
+/// Whether the character is a combining mark or an invisible formatting
+/// character, as rejected by [`Options::reject_combining`].
+#[cfg(feature = "unicode-properties")]
+fn is_combining(ch: char) -> bool {
+    use unicode_properties::{GeneralCategory, GeneralCategoryGroup, UnicodeGeneralCategory};
+    ch.general_category_group() == GeneralCategoryGroup::Mark
+        || ch.general_category() == GeneralCategory::Format
+}
+
+/// Whether the character is a Unicode noncharacter, as rejected by
+/// [`Options::reject_noncharacters`]: U+FDD0 to U+FDEF, or the last two code
+/// points of any plane (e.g. U+FFFE and U+FFFF).
+#[inline]
+const fn is_noncharacter(ch: char) -> bool {
+    let num = ch as u32;
+    matches!(num, 0xFDD0..=0xFDEF) || num & 0xFFFE == 0xFFFE
+}
+
+/// What to do with an escape sequence for an invalid code point, as decided by
+/// [`Options::on_invalid_unicode`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InvalidAction {
+    /// Return [`Error::InvalidUnicode`].
+    Error,
+    /// Drop the escape sequence without producing a character.
+    Skip,
+    /// Produce the character in place of the escape sequence.
+    Replace(char),
+}
+
+/// The behavior for a string ending in a backslash without a following escape
+/// sequence.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TrailingPolicy {
+    /// Return [`Error::IncompleteSequence`].
+    #[default]
+    Error,
+    /// Keep the backslash as a literal backslash.
+    Literal,
+    /// Drop the backslash without producing a character.
+    Ignore,
+}
+
+/// A set of escape sequences to recognize, as used by a particular language or
+/// format.
+///
+/// More dialects may be added in the future, so matches on it need a wildcard
+/// arm.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum Dialect {
+    /// The C-style escape sequences of
+    /// [`default_escape_sequence`](crate::default_escape_sequence).
+    #[default]
+    C,
+    /// The escape sequences of TOML basic strings: `\b`, `\t`, `\n`, `\f`,
+    /// `\r`, `\"`, `\\`, `\uNNNN`, and `\UNNNNNNNN`.
+    ///
+    /// The line ending backslash of multi-line basic strings is enabled by
+    /// [`Options::trim_line_continuation`].
+    Toml,
+    /// The escape sequences of JSON strings: `\"`, `\\`, `\/`, `\b`, `\f`,
+    /// `\n`, `\r`, `\t`, and `\uNNNN` (combining surrogate pairs).
+    Json,
+    /// The escape sequences of Rust string literals: `\n`, `\r`, `\t`, `\\`,
+    /// `\0`, `\'`, `\"`, `\xNN` (up to `\x7F`), and `\u{NN...}`.
+    ///
+    /// A backslash followed by a line break skips the line break and any
+    /// whitespace after it.
+    Rust,
+    /// No escape sequences at all: a backslash escapes the character after it,
+    /// which is kept literally, so e.g. `\n` is `n` and `\\` is `\`.
+    ///
+    /// A backslash followed by a line break keeps the line break, unless
+    /// [`Options::trim_line_continuation`] is enabled.
+    Minimal,
+    /// The escape sequences of PostgreSQL escape strings (`E'...'`): `\b`,
+    /// `\f`, `\n`, `\r`, `\t`, octal `\o` to `\ooo`, `\xh` or `\xhh`,
+    /// `\uNNNN`, and `\UNNNNNNNN`; a backslash followed by any other character
+    /// keeps that character literally.
+    ///
+    /// A doubled quote `''` in raw text is a literal quote, which is handled
+    /// only by [`Options::unescape`], since escape sequence parsers never see
+    /// raw text. Octal and hex escapes produce the character with that code
+    /// point, as in the C dialect, rather than a raw byte.
+    Postgres,
+    /// The escape sequences of double-quoted strings in the `snailquote`
+    /// crate: `\a`, `\b`, `\v`, `\f`, `\n`, `\r`, `\t`, `\e` and `\E` (escape),
+    /// `\\`, `\'`, `\"`, `\$`, `` \` ``, `\ ` (space), and `\u{NN...}`.
+    ///
+    /// Unlike the C dialect, there are no `\xNN`, `\uNNNN`, or octal escapes,
+    /// and a backslash followed by a line break is an error. The quoting of
+    /// `snailquote` is handled by [`unescape_snailquote`].
+    Snailquote,
+    /// The escape sequences of Java (and Kotlin) string literals: `\b`, `\t`,
+    /// `\n`, `\f`, `\r`, `\"`, `\'`, `\\`, octal `\0` to `\377`, and
+    /// `\uNNNN` (combining surrogate pairs).
+    ///
+    /// As in Java, any number of `u`s may follow the backslash of a `\u`
+    /// escape (e.g. `\uuu0041`), and octal escapes stop before exceeding
+    /// `\377`, so `\400` is `\40` followed by a `0`. There are no `\x` or `\U`
+    /// escapes.
+    Java,
+}
+
+impl Dialect {
+    /// Lists the escape sequences of the dialect, each with an example and
+    /// what it unescapes to, e.g. for generating reference documentation.
+    ///
+    /// ```
+    /// # use unescape_zero_copy::Dialect;
+    /// let tab = Dialect::Json.supported_escapes()
+    ///     .iter()
+    ///     .find(|spec| spec.name == "tab")
+    ///     .unwrap();
+    /// assert_eq!((tab.example, tab.decoded), ("\\t", "\t"));
+    /// ```
+    pub const fn supported_escapes(self) -> &'static [EscapeSpec] {
+        match self {
+            Self::C => C_ESCAPES,
+            Self::Toml => TOML_ESCAPES,
+            Self::Json => JSON_ESCAPES,
+            Self::Rust => RUST_ESCAPES,
+            Self::Minimal => MINIMAL_ESCAPES,
+            Self::Postgres => POSTGRES_ESCAPES,
+            Self::Snailquote => SNAILQUOTE_ESCAPES,
+            Self::Java => JAVA_ESCAPES,
+        }
+    }
+
+    /// Whether a `\uNNNN` high surrogate followed by a `\uNNNN` low surrogate
+    /// is combined into one character.
+    const fn pairs_surrogates(self) -> bool {
+        matches!(self, Self::C | Self::Json | Self::Postgres | Self::Java)
+    }
+}
+
+/// A description of an escape sequence supported by a [`Dialect`], as listed
+/// by [`Dialect::supported_escapes`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EscapeSpec {
+    /// A short name for the escape sequence, e.g. `"line feed"`.
+    pub name: &'static str,
+    /// An example of the escape sequence, including the backslash.
+    pub example: &'static str,
+    /// What the example unescapes to.
+    pub decoded: &'static str,
+}
+
+impl EscapeSpec {
+    const fn new(name: &'static str, example: &'static str, decoded: &'static str) -> Self {
+        Self {
+            name,
+            example,
+            decoded,
+        }
+    }
+}
+
+const C_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("bell", "\\a", "\x07"),
+    EscapeSpec::new("backspace", "\\b", "\x08"),
+    EscapeSpec::new("form feed", "\\f", "\x0C"),
+    EscapeSpec::new("line feed", "\\n", "\n"),
+    EscapeSpec::new("carriage return", "\\r", "\r"),
+    EscapeSpec::new("tab", "\\t", "\t"),
+    EscapeSpec::new("vertical tab", "\\v", "\x0B"),
+    EscapeSpec::new("backslash", "\\\\", "\\"),
+    EscapeSpec::new("single quote", "\\'", "'"),
+    EscapeSpec::new("double quote", "\\\"", "\""),
+    EscapeSpec::new("slash", "\\/", "/"),
+    EscapeSpec::new("line continuation", "\\\n", "\n"),
+    EscapeSpec::new("hex", "\\x41", "A"),
+    EscapeSpec::new("4-digit Unicode", "\\u00e9", "\u{e9}"),
+    EscapeSpec::new("surrogate pair", "\\uD83D\\uDE00", "\u{1F600}"),
+    EscapeSpec::new("8-digit Unicode", "\\U0001F600", "\u{1F600}"),
+    EscapeSpec::new("braced Unicode", "\\u{1F600}", "\u{1F600}"),
+    EscapeSpec::new("octal", "\\101", "A"),
+];
+
+const TOML_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("backspace", "\\b", "\x08"),
+    EscapeSpec::new("tab", "\\t", "\t"),
+    EscapeSpec::new("line feed", "\\n", "\n"),
+    EscapeSpec::new("form feed", "\\f", "\x0C"),
+    EscapeSpec::new("carriage return", "\\r", "\r"),
+    EscapeSpec::new("double quote", "\\\"", "\""),
+    EscapeSpec::new("backslash", "\\\\", "\\"),
+    EscapeSpec::new("4-digit Unicode", "\\u00e9", "\u{e9}"),
+    EscapeSpec::new("8-digit Unicode", "\\U0001F600", "\u{1F600}"),
+];
+
+const JSON_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("double quote", "\\\"", "\""),
+    EscapeSpec::new("backslash", "\\\\", "\\"),
+    EscapeSpec::new("slash", "\\/", "/"),
+    EscapeSpec::new("backspace", "\\b", "\x08"),
+    EscapeSpec::new("form feed", "\\f", "\x0C"),
+    EscapeSpec::new("line feed", "\\n", "\n"),
+    EscapeSpec::new("carriage return", "\\r", "\r"),
+    EscapeSpec::new("tab", "\\t", "\t"),
+    EscapeSpec::new("4-digit Unicode", "\\u00e9", "\u{e9}"),
+    EscapeSpec::new("surrogate pair", "\\uD83D\\uDE00", "\u{1F600}"),
+];
+
+const RUST_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("line feed", "\\n", "\n"),
+    EscapeSpec::new("carriage return", "\\r", "\r"),
+    EscapeSpec::new("tab", "\\t", "\t"),
+    EscapeSpec::new("backslash", "\\\\", "\\"),
+    EscapeSpec::new("null", "\\0", "\0"),
+    EscapeSpec::new("single quote", "\\'", "'"),
+    EscapeSpec::new("double quote", "\\\"", "\""),
+    EscapeSpec::new("ASCII hex", "\\x41", "A"),
+    EscapeSpec::new("braced Unicode", "\\u{1F600}", "\u{1F600}"),
+    EscapeSpec::new("line continuation", "\\\n    ", ""),
+];
+
+const MINIMAL_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("any character", "\\n", "n"),
+    EscapeSpec::new("backslash", "\\\\", "\\"),
+    EscapeSpec::new("line break", "\\\n", "\n"),
+];
+
+const POSTGRES_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("backspace", "\\b", "\x08"),
+    EscapeSpec::new("form feed", "\\f", "\x0C"),
+    EscapeSpec::new("line feed", "\\n", "\n"),
+    EscapeSpec::new("carriage return", "\\r", "\r"),
+    EscapeSpec::new("tab", "\\t", "\t"),
+    EscapeSpec::new("octal", "\\101", "A"),
+    EscapeSpec::new("hex", "\\x41", "A"),
+    EscapeSpec::new("4-digit Unicode", "\\u00e9", "\u{e9}"),
+    EscapeSpec::new("surrogate pair", "\\uD83D\\uDE00", "\u{1F600}"),
+    EscapeSpec::new("8-digit Unicode", "\\U0001F600", "\u{1F600}"),
+    EscapeSpec::new("any other character", "\\q", "q"),
+];
+
+const SNAILQUOTE_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("bell", "\\a", "\x07"),
+    EscapeSpec::new("backspace", "\\b", "\x08"),
+    EscapeSpec::new("vertical tab", "\\v", "\x0B"),
+    EscapeSpec::new("form feed", "\\f", "\x0C"),
+    EscapeSpec::new("line feed", "\\n", "\n"),
+    EscapeSpec::new("carriage return", "\\r", "\r"),
+    EscapeSpec::new("tab", "\\t", "\t"),
+    EscapeSpec::new("escape", "\\e", "\x1B"),
+    EscapeSpec::new("escape", "\\E", "\x1B"),
+    EscapeSpec::new("backslash", "\\\\", "\\"),
+    EscapeSpec::new("single quote", "\\'", "'"),
+    EscapeSpec::new("double quote", "\\\"", "\""),
+    EscapeSpec::new("dollar sign", "\\$", "$"),
+    EscapeSpec::new("backtick", "\\`", "`"),
+    EscapeSpec::new("space", "\\ ", " "),
+    EscapeSpec::new("braced Unicode", "\\u{1F600}", "\u{1F600}"),
+];
+
+const JAVA_ESCAPES: &[EscapeSpec] = &[
+    EscapeSpec::new("backspace", "\\b", "\x08"),
+    EscapeSpec::new("tab", "\\t", "\t"),
+    EscapeSpec::new("line feed", "\\n", "\n"),
+    EscapeSpec::new("form feed", "\\f", "\x0C"),
+    EscapeSpec::new("carriage return", "\\r", "\r"),
+    EscapeSpec::new("double quote", "\\\"", "\""),
+    EscapeSpec::new("single quote", "\\'", "'"),
+    EscapeSpec::new("backslash", "\\\\", "\\"),
+    EscapeSpec::new("octal", "\\101", "A"),
+    EscapeSpec::new("4-digit Unicode", "\\u00e9", "\u{e9}"),
+    EscapeSpec::new("surrogate pair", "\\uD83D\\uDE00", "\u{1F600}"),
+];
+
+/// Options for a configurable version of the default escape sequence parser.
+///
+/// With all options at their defaults, escape sequences are parsed exactly as
+/// [`default_escape_sequence`] does. The parser is available as
+/// [`Options::escape_sequence`], which can be passed to
+/// [`Unescape::new`](crate::Unescape::new) as `|s| options.escape_sequence(s)`,
+/// or strings can be unescaped directly with [`Options::unescape`]. Options
+/// covering the whole string, such as [`Options::strip_bom`] and the limits on
+/// input, are only enforced by [`Options::unescape`] and [`Options::unescaper`].
+///
+/// The tables of characters, such as [`Options::custom_escapes`], are borrowed
+/// for `'t`, so they can be built at runtime (e.g. from a configuration file)
+/// as well as written as constants.
+#[derive(Clone, Copy)]
+pub struct Options<'t> {
+    /// Which escape sequences to recognize; defaults to [`Dialect::C`].
+    pub dialect: Dialect,
+    /// What to do with a backslash at the very end of the string; defaults to
+    /// [`TrailingPolicy::Error`].
+    pub trailing_backslash: TrailingPolicy,
+    /// Whether to trim ASCII whitespace inside the braces of a `\u{...}`
+    /// escape, so e.g. `\u{ 61 }` unescapes to `a`.
+    ///
+    /// This is a leniency for hand-edited sources; by default, whitespace in
+    /// the braces is an invalid digit.
+    pub trim_brace_whitespace: bool,
+    /// Whether a backslash at the end of a line trims the line break along
+    /// with all whitespace up to the next non-whitespace character, producing
+    /// no character.
+    ///
+    /// This is the behavior of TOML's multi-line basic strings; spaces and
+    /// tabs are allowed between the backslash and the line break. When
+    /// disabled, a backslash followed by a line break is handled by the
+    /// dialect (the C dialect keeps the line break).
+    pub trim_line_continuation: bool,
+    /// Whether to accept the non-standard `\u+NN...` form, reading hex digits
+    /// until the first non-hex character.
+    pub unicode_plus_form: bool,
+    /// Whether `\XNN` is accepted as a hex escape like `\xNN`, as some tools
+    /// do. C only recognizes the lowercase form.
+    pub allow_uppercase_x: bool,
+    /// Whether `\x{NN...}` is accepted as a hex escape with any number of
+    /// digits, as in Perl and C++23 and as written by
+    /// [`escape_braced`](crate::escape_braced).
+    ///
+    /// C has no braced form, so this is disabled by default; it only applies
+    /// to the C dialect.
+    pub allow_braced_x: bool,
+    /// Whether to remove a byte order mark (U+FEFF) from the start of the
+    /// string, as some editors add when saving files.
+    ///
+    /// Only a literal BOM is removed (not an escaped one), and only by
+    /// [`Options::unescape`] and [`Options::unescaper`], since escape sequence
+    /// parsers never see raw text.
+    pub strip_bom: bool,
+    /// Whether [`Options::unescape`] and [`Options::unescaper`] return
+    /// [`Error::DisallowedChar`] for a byte order mark (U+FEFF) anywhere but
+    /// the very start of the string, as a misplaced BOM usually comes from
+    /// carelessly joined files.
+    ///
+    /// Both literal and escaped BOMs are checked. A BOM at the start is still
+    /// allowed (and kept, unless [`Options::strip_bom`] is enabled).
+    pub reject_interior_bom: bool,
+    /// Whether a `\u{...}` high surrogate immediately followed by a `\u{...}`
+    /// low surrogate is combined into one character, as with `\uNNNN` pairs.
+    ///
+    /// This is for interoperating with broken encoders; since `\u{...}` should
+    /// hold a whole code point, braced surrogates are an error by default.
+    pub combine_braced_surrogates: bool,
+    /// Whether producing a NUL character returns [`Error::NulByte`], so the
+    /// result can be passed to C APIs expecting NUL-terminated strings.
+    ///
+    /// Escaped NULs (e.g. `\0` or `\x00`) are always rejected, but literal
+    /// NULs only by [`Options::unescape`] and [`Options::unescaper`], as with
+    /// [`Options::strip_bom`].
+    pub reject_nul: bool,
+    /// Whether escape sequences producing Unicode noncharacters (such as
+    /// `\u{FFFE}` or `\u{FDD0}`) return [`Error::Noncharacter`], as some
+    /// strict interchange formats forbid them.
+    ///
+    /// Only escaped characters are checked, not literal ones. Noncharacters
+    /// are valid [`char`]s, so they're allowed by default.
+    pub reject_noncharacters: bool,
+    /// Whether to accept a `U+` prefix (in either case) inside the braces of a
+    /// `\u{...}` escape, so e.g. `\u{U+1F600}` as copied from a Unicode chart
+    /// unescapes to U+1F600.
+    pub accept_u_plus_prefix: bool,
+    /// The longest string in bytes accepted by [`Options::unescape`] and
+    /// [`Options::unescaper`], beyond which [`Error::LimitExceeded`] is
+    /// returned; unlimited by default.
+    pub max_input_len: Option<usize>,
+    /// The most escape sequences accepted by [`Options::unescape`] and
+    /// [`Options::unescaper`], beyond which [`Error::LimitExceeded`] is
+    /// returned; unlimited by default.
+    ///
+    /// Together with [`Options::max_input_len`], this bounds the work done on
+    /// untrusted input.
+    pub max_escapes: Option<usize>,
+    /// The longest run of escape sequences with no raw text between them
+    /// accepted by [`Options::unescape`] and [`Options::unescaper`], beyond
+    /// which [`Error::LimitExceeded`] is returned; unlimited by default.
+    ///
+    /// Unlike [`Options::max_escapes`], this allows any number of escape
+    /// sequences spread through text, as a cheap check for input made of
+    /// little but escape sequences.
+    pub max_consecutive_escapes: Option<usize>,
+    /// Extra single-character escape sequences, as pairs of the character
+    /// after the backslash and the character it unescapes to; e.g.
+    /// `&[('N', '\n')]` makes `\N` a line break.
+    ///
+    /// These are looked up before the escape sequences of the dialect, so they
+    /// take precedence over built-in sequences starting with the same
+    /// character. A trailing backslash and line continuations are still
+    /// handled first.
+    ///
+    /// ```
+    /// use unescape_zero_copy::Options;
+    ///
+    /// // `\N` as a newline, as in some legacy record formats
+    /// const LEGACY: Options = Options {
+    ///     custom_escapes: &[('N', '\n')],
+    ///     ..Options::new()
+    /// };
+    /// assert_eq!(LEGACY.unescape(r"a\Nb\nc").unwrap(), "a\nb\nc");
+    /// ```
+    pub custom_escapes: &'t [(char, char)],
+    /// Whether [`Options::escape_fragment`], [`Options::unescaper`] and
+    /// [`Options::unescape`] keep unknown escape sequences as
+    /// [`StringFragment::Verbatim`] text rather than returning an error, for
+    /// editing tools which must write documents back out unchanged.
+    ///
+    /// Only the backslash and the character after it are kept; the rest of
+    /// the string is parsed as usual.
+    pub preserve_unknown: bool,
+    /// Characters whose escape sequences are kept as-is, backslash included,
+    /// rather than unescaped; e.g. `&['*', '[']` keeps `\*` and `\[` for
+    /// markup which a later stage unescapes.
+    ///
+    /// These are checked before any other escape sequence, and produce
+    /// [`StringFragment::Verbatim`] text from [`Options::escape_fragment`].
+    ///
+    /// ```
+    /// use unescape_zero_copy::Options;
+    ///
+    /// const MARKUP: Options = Options {
+    ///     retain_backslash: &['*', '['],
+    ///     ..Options::new()
+    /// };
+    /// assert_eq!(MARKUP.unescape(r"\*a\*\n").unwrap(), "\\*a\\*\n");
+    /// ```
+    pub retain_backslash: &'t [char],
+    /// The only characters which may be escaped, each escaping itself, for
+    /// minimal formats where e.g. only the delimiter can be escaped; any other
+    /// escape sequence is an [`Error::UnknownSequence`] (or kept as-is with
+    /// [`Options::preserve_unknown`]). Unrestricted by default.
+    ///
+    /// This replaces the escape sequences of the dialect, so a backslash must
+    /// be listed to be escapable itself; [`Options::custom_escapes`], line
+    /// continuations, and the doubled quotes of [`Dialect::Postgres`] are
+    /// still handled.
+    ///
+    /// ```
+    /// use unescape_zero_copy::{Error, Options};
+    ///
+    /// const RAW: Options = Options {
+    ///     only_escape: Some(&['"']),
+    ///     ..Options::new()
+    /// };
+    /// assert_eq!(RAW.unescape(r#"say \"hi\""#).unwrap(), r#"say "hi""#);
+    /// assert_eq!(RAW.unescape(r"a\n"), Err(Error::UnknownSequence('n')));
+    /// ```
+    pub only_escape: Option<&'t [char]>,
+    /// A character to produce in place of escape sequences with an invalid
+    /// code point (e.g. an unpaired surrogate), instead of returning
+    /// [`Error::InvalidUnicode`].
+    ///
+    /// Other errors, such as unknown escape sequences or code points too
+    /// large for a `u32`, are still returned.
+    pub invalid_unicode_replacement: Option<char>,
+    /// A function deciding what to do with each escape sequence with an
+    /// invalid code point, given the code point; this takes precedence over
+    /// [`Options::invalid_unicode_replacement`]. Being borrowed for `'t`, it
+    /// can be a closure capturing state, such as a count of invalid escapes.
+    ///
+    /// ```
+    /// use unescape_zero_copy::{InvalidAction, Options};
+    ///
+    /// // drop lone surrogates, but reject code points beyond Unicode
+    /// const LENIENT: Options = Options {
+    ///     on_invalid_unicode: Some(&|num| match num {
+    ///         0xD800..=0xDFFF => InvalidAction::Skip,
+    ///         _ => InvalidAction::Error,
+    ///     }),
+    ///     ..Options::new()
+    /// };
+    /// assert_eq!(LENIENT.unescape(r"a\uD800b").unwrap(), "ab");
+    /// assert!(LENIENT.unescape(r"\u{110000}").is_err());
+    /// ```
+    pub on_invalid_unicode: Option<&'t dyn Fn(u32) -> InvalidAction>,
+    /// Whether the unbraced `\u` form reads up to six hex digits (as many as
+    /// are present) instead of exactly four, as some encoders write characters
+    /// outside the Basic Multilingual Plane as e.g. `\u1F600`.
+    ///
+    /// This is non-standard, and changes the meaning of a four-digit escape
+    /// followed by a hex digit, so it's disabled by default.
+    pub unicode_wide: bool,
+    /// A character starting a comment, after which [`Options::unescape`]
+    /// keeps the rest of the line as-is, without unescaping it.
+    ///
+    /// Only a literal comment character starts a comment, not one produced by
+    /// an escape sequence; to allow escaping it, add it to
+    /// [`Options::custom_escapes`].
+    pub comment_char: Option<char>,
+    /// Whether escape sequences producing combining marks or invisible
+    /// formatting characters (such as `\u{301}` or a zero-width joiner) return
+    /// [`Error::DisallowedChar`], against homograph attacks.
+    ///
+    /// Only escaped characters are checked, not literal ones. This needs the
+    /// `unicode-properties` feature, for the general category of characters.
+    #[cfg(feature = "unicode-properties")]
+    pub reject_combining: bool,
+}
+
+impl<'t> Options<'t> {
+    /// Creates the default options.
+    pub const fn new() -> Self {
+        Self {
+            dialect: Dialect::C,
+            trailing_backslash: TrailingPolicy::Error,
+            trim_brace_whitespace: false,
+            trim_line_continuation: false,
+            unicode_plus_form: false,
+            allow_uppercase_x: false,
+            allow_braced_x: false,
+            strip_bom: false,
+            reject_interior_bom: false,
+            combine_braced_surrogates: false,
+            reject_nul: false,
+            reject_noncharacters: false,
+            accept_u_plus_prefix: false,
+            max_input_len: None,
+            max_escapes: None,
+            max_consecutive_escapes: None,
+            custom_escapes: &[],
+            preserve_unknown: false,
+            retain_backslash: &[],
+            only_escape: None,
+            invalid_unicode_replacement: None,
+            on_invalid_unicode: None,
+            unicode_wide: false,
+            comment_char: None,
+            #[cfg(feature = "unicode-properties")]
+            reject_combining: false,
+        }
+    }
+
+    /// Parses an escape sequence according to the options.
+    ///
+    /// Like [`default_escape_sequence`], this is called after a backslash has
+    /// been found, and returns the unescaped character (if any) and the
+    /// remaining input.
+    pub fn escape_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        if s.is_empty() {
+            return match self.trailing_backslash {
+                TrailingPolicy::Error => Err(Error::IncompleteSequence),
+                TrailingPolicy::Literal => Ok((Some('\\'), s)),
+                TrailingPolicy::Ignore => Ok((None, s)),
+            };
+        }
+        if self.trim_line_continuation || self.dialect == Dialect::Rust {
+            let line = if self.trim_line_continuation {
+                s.trim_start_matches([' ', '\t'])
+            } else {
+                s
+            };
+            if line.starts_with('\n') || line.starts_with("\r\n") {
+                return Ok((None, line.trim_start_matches([' ', '\t', '\r', '\n'])));
+            }
+        }
+        let parsed = match (self.custom_sequence(s), self.only_escape) {
+            (Some(parsed), _) => parsed,
+            (None, Some(escapable)) => only_sequence(s, escapable)?,
+            (None, None) => match self.parse_sequence(s)? {
+                (Some(ch), rem) => (ch, rem),
+                // an invalid code point skipped by `on_invalid_unicode`
+                (None, rem) => return Ok((None, rem)),
+            },
+        };
+        match parsed {
+            ('\0', _) if self.reject_nul => Err(Error::NulByte),
+            (ch, _) if self.reject_noncharacters && is_noncharacter(ch) => {
+                Err(Error::Noncharacter(ch as u32))
+            }
+            #[cfg(feature = "unicode-properties")]
+            (ch, _) if self.reject_combining && is_combining(ch) => Err(Error::DisallowedChar(ch)),
+            (ch, rem) => Ok((Some(ch), rem)),
+        }
+    }
+
+    /// Looks up an escape sequence in [`Options::custom_escapes`].
+    #[inline]
+    fn custom_sequence<'a>(&self, s: &'a str) -> Option<(char, &'a str)> {
+        let next = s.chars().next()?;
+        let &(_, ch) = self
+            .custom_escapes
+            .iter()
+            .find(|&&(from, _)| from == next)?;
+        Some((ch, &s[next.len_utf8()..]))
+    }
+
+    /// Parses an escape sequence as [`Options::escape_sequence`] does, but
+    /// produces a [`StringFragment`], with surrogates from `\u` escapes given
+    /// as [`StringFragment::HighSurrogate`] or [`StringFragment::LowSurrogate`]
+    /// halves rather than combined or rejected.
+    ///
+    /// This is for deliberately handling broken encodings, such as unpaired
+    /// surrogates; the halves can be combined with [`join_surrogates`].
+    ///
+    /// ```
+    /// # use unescape_zero_copy::{Options, StringFragment, Unescape};
+    /// let options = Options::new();
+    /// let mut unescaper = Unescape::new(|s| options.escape_fragment(s), r"\uD83Dx");
+    /// assert_eq!(
+    ///     unescaper.next_fragment(),
+    ///     Some(Ok(StringFragment::HighSurrogate(0xD83D)))
+    /// );
+    /// assert_eq!(unescaper.next_fragment(), Some(Ok(StringFragment::Raw("x"))));
+    /// ```
+    pub fn escape_fragment<'a>(&self, s: &'a str) -> Result<(StringFragment<'a>, &'a str), Error> {
+        if let Some(retained) = self.retained_sequence(s) {
+            return Ok(retained);
+        }
+        if let Some(digits) = s.strip_prefix('u').filter(|_| self.only_escape.is_none()) {
+            let parsed = match (digits.strip_prefix('{'), self.dialect) {
+                (Some(braced), Dialect::C | Dialect::Rust | Dialect::Snailquote) => {
+                    self.braced_hex(braced).ok()
+                }
+                (
+                    None,
+                    Dialect::C | Dialect::Toml | Dialect::Json | Dialect::Postgres | Dialect::Java,
+                ) => self.bare_hex(digits).ok(),
+                _ => None,
+            };
+            if let Some((num @ 0xD800..=0xDFFF, rem)) = parsed {
+                // surrogates fit in 16 bits
+                let fragment = if is_high_surrogate(num) {
+                    StringFragment::HighSurrogate(num as u16)
+                } else {
+                    StringFragment::LowSurrogate(num as u16)
+                };
+                return Ok((fragment, rem));
+            }
+        }
+        self.sequence_fragment(s)
+    }
+
+    /// Parses an escape sequence as [`Options::escape_sequence`] does, keeping
+    /// an unknown one as [`StringFragment::Verbatim`] text with
+    /// [`Options::preserve_unknown`].
+    #[inline]
+    fn sequence_fragment<'a>(&self, s: &'a str) -> Result<(StringFragment<'a>, &'a str), Error> {
+        match self.escape_sequence(s) {
+            Err(Error::UnknownSequence(c) | Error::InvalidOctalDigit(c))
+                if self.preserve_unknown =>
+            {
+                Ok((
+                    StringFragment::Verbatim(&s[..c.len_utf8()]),
+                    &s[c.len_utf8()..],
+                ))
+            }
+            res => res.map(|(ch, rem)| (StringFragment::from(ch), rem)),
+        }
+    }
+
+    /// Keeps an escape sequence in [`Options::retain_backslash`] as
+    /// [`StringFragment::Verbatim`] text.
+    #[inline]
+    fn retained_sequence<'a>(&self, s: &'a str) -> Option<(StringFragment<'a>, &'a str)> {
+        let next = s.chars().next()?;
+        if !self.retain_backslash.contains(&next) {
+            return None;
+        }
+        let (retained, rem) = s.split_at(next.len_utf8());
+        Some((StringFragment::Verbatim(retained), rem))
+    }
+
+    /// Parses an escape sequence, giving `None` for an invalid code point
+    /// skipped by [`Options::on_invalid_unicode`].
+    pub(crate) fn parse_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        match self.dialect {
+            Dialect::C => self.c_sequence(s),
+            Dialect::Toml => self.toml_sequence(s),
+            Dialect::Json => self.json_sequence(s),
+            Dialect::Rust => self.rust_sequence(s),
+            Dialect::Postgres => self.postgres_sequence(s),
+            Dialect::Snailquote => self.snailquote_sequence(s),
+            Dialect::Java => self.java_sequence(s),
+            Dialect::Minimal => {
+                let mut chars = s.chars();
+                let next = chars.next().ok_or(Error::IncompleteSequence)?;
+                Ok((Some(next), chars.as_str()))
+            }
+        }
+    }
+
+    fn c_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        let mut chars = s.chars();
+        let next = chars.next().ok_or(Error::IncompleteSequence)?;
+        if let Some(ch) = simple_escape(next) {
+            return Ok((Some(ch), chars.as_str()));
+        }
+        match next {
+            'x' => match chars
+                .as_str()
+                .strip_prefix('{')
+                .filter(|_| self.allow_braced_x)
+            {
+                Some(braced) => {
+                    let (num, rem) = self.braced_hex(braced)?;
+                    Ok((self.code_point(num)?, rem))
+                }
+                None => self.unicode_char(chars.as_str(), 2),
+            },
+            'X' if self.allow_uppercase_x => self.unicode_char(chars.as_str(), 2),
+            'u' => self.u_sequence(chars.as_str(), true),
+            'U' => self.unicode_char(chars.as_str(), 8),
+            _ => {
+                let count = s.chars().take_while(|n| n.is_digit(8)).count().min(3);
+                if count > 0 {
+                    let num = u32::from_str_radix(&s[0..count], 8)?;
+                    Ok((self.code_point(num)?, &s[count..]))
+                } else if let '8' | '9' = next {
+                    Err(Error::InvalidOctalDigit(next))
+                } else {
+                    Err(Error::UnknownSequence(next))
+                }
+            }
+        }
+    }
+
+    fn toml_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        let mut chars = s.chars();
+        let next = chars.next().ok_or(Error::IncompleteSequence)?;
+        match next {
+            'b' => Ok((Some('\x08'), chars.as_str())),
+            't' => Ok((Some('\t'), chars.as_str())),
+            'n' => Ok((Some('\n'), chars.as_str())),
+            'f' => Ok((Some('\x0C'), chars.as_str())),
+            'r' => Ok((Some('\r'), chars.as_str())),
+            '"' | '\\' => Ok((Some(next), chars.as_str())),
+            'u' => self.u_sequence(chars.as_str(), false),
+            'U' => self.unicode_char(chars.as_str(), 8),
+            _ => Err(Error::UnknownSequence(next)),
+        }
+    }
+
+    fn json_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        let mut chars = s.chars();
+        let next = chars.next().ok_or(Error::IncompleteSequence)?;
+        match next {
+            '"' | '\\' | '/' => Ok((Some(next), chars.as_str())),
+            'b' => Ok((Some('\x08'), chars.as_str())),
+            'f' => Ok((Some('\x0C'), chars.as_str())),
+            'n' => Ok((Some('\n'), chars.as_str())),
+            'r' => Ok((Some('\r'), chars.as_str())),
+            't' => Ok((Some('\t'), chars.as_str())),
+            'u' => self.u_sequence(chars.as_str(), false),
+            _ => Err(Error::UnknownSequence(next)),
+        }
+    }
+
+    fn snailquote_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        let mut chars = s.chars();
+        let next = chars.next().ok_or(Error::IncompleteSequence)?;
+        match next {
+            'a' => Ok((Some('\x07'), chars.as_str())),
+            'b' => Ok((Some('\x08'), chars.as_str())),
+            'v' => Ok((Some('\x0B'), chars.as_str())),
+            'f' => Ok((Some('\x0C'), chars.as_str())),
+            'n' => Ok((Some('\n'), chars.as_str())),
+            'r' => Ok((Some('\r'), chars.as_str())),
+            't' => Ok((Some('\t'), chars.as_str())),
+            'e' | 'E' => Ok((Some('\x1B'), chars.as_str())),
+            '\\' | '\'' | '"' | '$' | '`' | ' ' => Ok((Some(next), chars.as_str())),
+            'u' => match chars.as_str().strip_prefix('{') {
+                Some(braced) => {
+                    let (num, rem) = self.braced_hex(braced)?;
+                    Ok((self.code_point(num)?, rem))
+                }
+                None => Err(Error::IncompleteUnicode),
+            },
+            _ => Err(Error::UnknownSequence(next)),
+        }
+    }
+
+    fn java_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        let mut chars = s.chars();
+        let next = chars.next().ok_or(Error::IncompleteSequence)?;
+        match next {
+            'b' => Ok((Some('\x08'), chars.as_str())),
+            't' => Ok((Some('\t'), chars.as_str())),
+            'n' => Ok((Some('\n'), chars.as_str())),
+            'f' => Ok((Some('\x0C'), chars.as_str())),
+            'r' => Ok((Some('\r'), chars.as_str())),
+            '"' | '\'' | '\\' => Ok((Some(next), chars.as_str())),
+            'u' => self.u_sequence(chars.as_str().trim_start_matches('u'), false),
+            '0'..='7' => {
+                // only `\0` to `\377`, so three digits must start with 0 to 3
+                let max = if next <= '3' { 3 } else { 2 };
+                let count = s
+                    .bytes()
+                    .take(max)
+                    .take_while(|b| matches!(b, b'0'..=b'7'))
+                    .count();
+                let num = u32::from_str_radix(&s[0..count], 8)?;
+                Ok((self.code_point(num)?, &s[count..]))
+            }
+            _ => Err(Error::UnknownSequence(next)),
+        }
+    }
+
+    fn rust_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        let mut chars = s.chars();
+        let next = chars.next().ok_or(Error::IncompleteSequence)?;
+        match next {
+            'n' => Ok((Some('\n'), chars.as_str())),
+            'r' => Ok((Some('\r'), chars.as_str())),
+            't' => Ok((Some('\t'), chars.as_str())),
+            '0' => Ok((Some('\0'), chars.as_str())),
+            '\\' | '\'' | '"' => Ok((Some(next), chars.as_str())),
+            'x' => {
+                let (num, rem) = hex_prefix(chars.as_str(), 2)?;
+                if num > 0x7F {
+                    return Ok((self.invalid_unicode(num)?, rem));
+                }
+                Ok((self.code_point(num)?, rem))
+            }
+            // Rust only has the braced form of `\u`
+            'u' if chars.as_str().starts_with('{') => self.u_sequence(chars.as_str(), true),
+            _ => Err(Error::UnknownSequence(next)),
+        }
+    }
+
+    fn postgres_sequence<'a>(&self, s: &'a str) -> Result<(Option<char>, &'a str), Error> {
+        let mut chars = s.chars();
+        let next = chars.next().ok_or(Error::IncompleteSequence)?;
+        match next {
+            'b' => Ok((Some('\x08'), chars.as_str())),
+            'f' => Ok((Some('\x0C'), chars.as_str())),
+            'n' => Ok((Some('\n'), chars.as_str())),
+            'r' => Ok((Some('\r'), chars.as_str())),
+            't' => Ok((Some('\t'), chars.as_str())),
+            '0'..='7' => {
+                let count = s
+                    .bytes()
+                    .take_while(|b| matches!(b, b'0'..=b'7'))
+                    .count()
+                    .min(3);
+                let num = u32::from_str_radix(&s[0..count], 8)?;
+                Ok((self.code_point(num)?, &s[count..]))
+            }
+            'x' => {
+                let hex = chars.as_str();
+                let count = hex.bytes().take_while(u8::is_ascii_hexdigit).count();
+                match count.min(2) {
+                    // a lone `\x` is just an `x`
+                    0 => Ok((Some('x'), hex)),
+                    count => {
+                        let num = parse_hex(&hex[0..count])?;
+                        Ok((self.code_point(num)?, &hex[count..]))
+                    }
+                }
+            }
+            'u' => self.u_sequence(chars.as_str(), false),
+            'U' => self.unicode_char(chars.as_str(), 8),
+            _ => Ok((Some(next), chars.as_str())),
+        }
+    }
+
+    /// Parses a `\u` escape after the `u`, with `braced` for dialects
+    /// supporting `\u{...}`.
+    fn u_sequence<'a>(&self, s: &'a str, braced: bool) -> Result<(Option<char>, &'a str), Error> {
+        if let Some(s) = s.strip_prefix('{').filter(|_| braced) {
+            let (num, rem) = self.braced_hex(s)?;
+            if self.combine_braced_surrogates && is_high_surrogate(num) {
+                let low = rem
+                    .strip_prefix("\\u{")
+                    .and_then(|s| self.braced_hex(s).ok());
+                if let Some((ch, rem)) = low.and_then(|low| combine_surrogates(num, low)) {
+                    return Ok((Some(ch), rem));
+                }
+            }
+            Ok((self.code_point(num)?, rem))
+        } else if let Some(s) = s.strip_prefix('+').filter(|_| self.unicode_plus_form) {
+            let size = s.as_bytes()[..s.len().min(MAX_DIGITS + 1)]
+                .iter()
+                .position(|b| !b.is_ascii_hexdigit())
+                .unwrap_or(s.len().min(MAX_DIGITS + 1));
+            if size == 0 {
+                return Err(missing_digits(s));
+            } else if size > MAX_DIGITS {
+                return Err(Error::CodePointOutOfRange);
+            }
+            let num = parse_hex(&s[0..size])?;
+            Ok((self.code_point(num)?, &s[size..]))
+        } else {
+            let (num, rem) = self.bare_hex(s)?;
+            if self.dialect.pairs_surrogates() && is_high_surrogate(num) {
+                let low = rem.strip_prefix("\\u").and_then(|s| self.bare_hex(s).ok());
+                if let Some((ch, rem)) = low.and_then(|low| combine_surrogates(num, low)) {
+                    return Ok((Some(ch), rem));
+                }
+            }
+            Ok((self.code_point(num)?, rem))
+        }
+    }
+
+    /// Parses the digits of a `\uNNNN` escape, after the `u`.
+    fn bare_hex<'a>(&self, s: &'a str) -> Result<(u32, &'a str), Error> {
+        if !self.unicode_wide {
+            return hex_prefix(s, 4);
+        }
+        let size = s.bytes().take(6).take_while(u8::is_ascii_hexdigit).count();
+        if size == 0 {
+            return Err(missing_digits(s));
+        }
+        Ok((parse_hex(&s[0..size])?, &s[size..]))
+    }
+
+    /// Parses the contents of a `\u{...}` escape, after the opening brace.
+    fn braced_hex<'a>(&self, s: &'a str) -> Result<(u32, &'a str), Error> {
+        // only look for the closing brace so far, so a long run of digits in
+        // a large string doesn't need to be scanned to the end
+        let size = match s
+            .as_bytes()
+            .iter()
+            .take(MAX_DIGITS + 1)
+            .position(|&b| b == b'}')
+        {
+            Some(size) => size,
+            None if s.len() > MAX_DIGITS => return Err(Error::CodePointOutOfRange),
+            None => return Err(Error::IncompleteUnicode),
+        };
+        let mut digits = &s[0..size];
+        if self.trim_brace_whitespace {
+            digits = digits.trim_matches(|c: char| c.is_ascii_whitespace());
+        }
+        if self.accept_u_plus_prefix {
+            digits = digits
+                .strip_prefix("U+")
+                .or_else(|| digits.strip_prefix("u+"))
+                .unwrap_or(digits);
+        }
+        let num = parse_hex(digits)?;
+        Ok((num, &s[size + 1..]))
+    }
+
+    /// Converts the code point of an escape sequence to a character.
+    #[inline]
+    fn code_point(&self, num: u32) -> Result<Option<char>, Error> {
+        char::from_u32(num).map_or_else(|| self.invalid_unicode(num), |ch| Ok(Some(ch)))
+    }
+
+    /// Replaces an invalid code point according to
+    /// [`Options::on_invalid_unicode`] or
+    /// [`Options::invalid_unicode_replacement`].
+    ///
+    /// Skipped code points give `None`.
+    #[inline]
+    fn invalid_unicode(&self, num: u32) -> Result<Option<char>, Error> {
+        match self.on_invalid_unicode.map(|f| f(num)) {
+            Some(InvalidAction::Replace(ch)) => Ok(Some(ch)),
+            Some(InvalidAction::Skip) => Ok(None),
+            Some(InvalidAction::Error) => Err(Error::InvalidUnicode(num)),
+            None => self
+                .invalid_unicode_replacement
+                .map(Some)
+                .ok_or(Error::InvalidUnicode(num)),
+        }
+    }
+
+    /// Parses a Unicode escape of exactly `chars` hex digits.
+    #[inline]
+    fn unicode_char<'a>(&self, s: &'a str, chars: usize) -> Result<(Option<char>, &'a str), Error> {
+        let (num, rem) = hex_prefix(s, chars)?;
+        Ok((self.code_point(num)?, rem))
+    }
+
+    /// Makes an unescaper over the string using these options.
+    ///
+    /// Unlike passing [`Options::escape_sequence`] to
+    /// [`Unescape::new`](crate::Unescape::new), this enforces the options
+    /// covering the whole string: a literal BOM is stripped by
+    /// [`Options::strip_bom`], literal text is checked by
+    /// [`Options::reject_nul`] and [`Options::reject_interior_bom`], and
+    /// [`Options::max_input_len`] is checked up front, returning an error
+    /// before anything is unescaped; [`Options::max_escapes`] and
+    /// [`Options::max_consecutive_escapes`] are checked as escape sequences
+    /// are parsed. Raw text is still produced as-is, so the doubled quotes of
+    /// [`Dialect::Postgres`] and [`Options::comment_char`] are only handled by
+    /// [`Options::unescape`].
+    ///
+    /// ```
+    /// # use unescape_zero_copy::{Error, Options, StringFragment};
+    /// let opts = Options {
+    ///     max_escapes: Some(1),
+    ///     ..Options::new()
+    /// };
+    /// let mut unescaper = opts.unescaper(r"a\tb\n").unwrap();
+    /// assert_eq!(unescaper.next_fragment(), Some(Ok(StringFragment::Raw("a"))));
+    /// assert_eq!(unescaper.next_fragment(), Some(Ok(StringFragment::Escaped('\t'))));
+    /// assert_eq!(unescaper.next_fragment(), Some(Ok(StringFragment::Raw("b"))));
+    /// assert_eq!(unescaper.next_fragment(), Some(Err(Error::LimitExceeded)));
+    ///
+    /// let opts = Options {
+    ///     reject_nul: true,
+    ///     ..Options::new()
+    /// };
+    /// assert!(matches!(opts.unescaper("a\0b"), Err(Error::NulByte)));
+    /// ```
+    pub fn unescaper<'a>(
+        &self,
+        s: &'a str,
+    ) -> Result<
+        Unescape<'a, impl FnMut(&'a str) -> Parsed<'a> + 't, Error, StringFragment<'a>>,
+        Error,
+    > {
+        let (s, mut limits) = self.checked(s)?;
+        Ok(Unescape::new(move |s| limits.escape_sequence(s), s))
+    }
+
+    /// Checks the string against the options covering the whole string,
+    /// returning it without any stripped BOM, along with the state for
+    /// enforcing the limits on escape sequences.
+    fn checked<'a>(&self, input: &'a str) -> Result<(&'a str, Limits<'t>), Error> {
+        let s = if self.strip_bom {
+            input.strip_prefix(BOM).unwrap_or(input)
+        } else {
+            input
+        };
+        if self.max_input_len.is_some_and(|max| s.len() > max) {
+            return Err(Error::LimitExceeded);
+        }
+        if self.reject_nul && s.contains('\0') {
+            return Err(Error::NulByte);
+        }
+        // a stripped BOM still counts as the start of the string
+        if self.reject_interior_bom && input.char_indices().any(|(idx, c)| idx > 0 && c == BOM) {
+            return Err(Error::DisallowedChar(BOM));
+        }
+        let limits = Limits {
+            options: *self,
+            len: input.len(),
+            escapes: 0,
+            consecutive: 0,
+            last_rem: None,
+        };
+        Ok((s, limits))
+    }
+
+    /// Unescapes the string into a [`Cow`] string using these options.
+    ///
+    /// As with [`unescape`], this only allocates if escape sequences were
+    /// found.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn unescape<'a>(&self, s: &'a str) -> Result<Cow<'a, str>, Error> {
+        let (s, mut limits) = self.checked(s)?;
+        let escape_sequence = |s| limits.escape_sequence(s);
+        if self.dialect == Dialect::Postgres || self.comment_char.is_some() {
+            return self.unescape_raw_text(escape_sequence, s);
+        }
+        unescape(escape_sequence, s)
+    }
+
+    /// Unescapes the string as [`Options::unescape`] does, also handling the
+    /// doubled quotes of [`Dialect::Postgres`] and the comments of
+    /// [`Options::comment_char`] in raw text.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn unescape_raw_text<'a, F>(
+        &self,
+        mut escape_sequence: F,
+        s: &'a str,
+    ) -> Result<Cow<'a, str>, Error>
+    where
+        F: FnMut(&'a str) -> Result<(StringFragment<'a>, &'a str), Error>,
+    {
+        let mut out = Cow::Borrowed("");
+        let mut rest = s;
+        loop {
+            let mut unescaper = Unescape::new(&mut escape_sequence, rest);
+            let mut comment = None;
+            while let Some(fragment) = unescaper.next_fragment().transpose()? {
+                match fragment {
+                    StringFragment::Raw(mut raw) => {
+                        if let Some(idx) = self.comment_char.and_then(|c| raw.find(c)) {
+                            comment = Some(unescaper.position() - raw.len() + idx);
+                            raw = &raw[..idx];
+                        }
+                        if self.dialect == Dialect::Postgres && raw.contains("''") {
+                            let out = out.to_mut();
+                            for (idx, piece) in raw.split("''").enumerate() {
+                                if idx > 0 {
+                                    out.push('\'');
+                                }
+                                out.push_str(piece);
+                            }
+                        } else {
+                            out += raw;
+                        }
+                        if comment.is_some() {
+                            break;
+                        }
+                    }
+                    fragment => match fragment.text() {
+                        FragmentText::Str(s) => out += s,
+                        FragmentText::Char(c) => out.to_mut().push(c),
+                        FragmentText::Empty => (),
+                    },
+                }
+            }
+            let Some(start) = comment else {
+                return Ok(out);
+            };
+            // the comment is kept as-is, and escape sequences resume on the
+            // next line
+            let end = rest[start..]
+                .find('\n')
+                .map_or(rest.len(), |len| start + len);
+            out += &rest[start..end];
+            rest = &rest[end..];
+        }
+    }
+}
+
+/// The result of parsing an escape sequence into a fragment.
+type Parsed<'a> = Result<(StringFragment<'a>, &'a str), Error>;
+
+/// The options of a string being unescaped by [`Options::unescaper`] or
+/// [`Options::unescape`], along with the count of escape sequences so far.
+#[derive(Debug, Clone, Copy)]
+struct Limits<'t> {
+    options: Options<'t>,
+    /// The length of the whole string, including any stripped BOM.
+    len: usize,
+    escapes: usize,
+    /// The number of escape sequences in the current run without raw text.
+    consecutive: usize,
+    /// The length of the input left after the last escape sequence, which is
+    /// one more than the input after the backslash of the next one if there's
+    /// no raw text between them.
+    last_rem: Option<usize>,
+}
+
+impl Limits<'_> {
+    /// Parses an escape sequence as [`Options::escape_fragment`] does (though
+    /// without splitting surrogates into halves), also enforcing the limits on
+    /// escape sequences and rejecting an escaped BOM.
+    fn escape_sequence<'a>(&mut self, s: &'a str) -> Parsed<'a> {
+        let opts = &self.options;
+        self.escapes += 1;
+        self.consecutive = if self.last_rem == Some(s.len() + 1) {
+            self.consecutive + 1
+        } else {
+            1
+        };
+        if opts.max_escapes.is_some_and(|max| self.escapes > max)
+            || opts
+                .max_consecutive_escapes
+                .is_some_and(|max| self.consecutive > max)
+        {
+            return Err(Error::LimitExceeded);
+        }
+        let parsed = match opts.retained_sequence(s) {
+            Some(retained) => Ok(retained),
+            None => opts.sequence_fragment(s),
+        };
+        self.last_rem = parsed.as_ref().ok().map(|(_, rem)| rem.len());
+        match parsed {
+            // the backslash is only at the start if nothing precedes it
+            Ok((StringFragment::Escaped(BOM), _))
+                if opts.reject_interior_bom && s.len() + 1 < self.len =>
+            {
+                Err(Error::DisallowedChar(BOM))
+            }
+            parsed => parsed,
+        }
+    }
+}
+
+/// The byte order mark, checked for by [`Options::reject_interior_bom`].
+const BOM: char = '\u{FEFF}';
+
+/// Parses an escape sequence of [`Options::only_escape`], a character
+/// escaping itself.
+fn only_sequence<'a>(s: &'a str, escapable: &[char]) -> Result<(char, &'a str), Error> {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if escapable.contains(&c) => Ok((c, chars.as_str())),
+        Some(c) => Err(Error::UnknownSequence(c)),
+        None => Err(Error::IncompleteSequence),
+    }
+}
+
+/// The most characters read for a variable-length code point, after which
+/// [`Error::CodePointOutOfRange`] is returned; this is plenty for leading
+/// zeros and whitespace.
+pub(crate) const MAX_DIGITS: usize = 32;
+
+/// Unescapes a string quoted as by the `snailquote` crate, as a drop-in
+/// replacement for its `unescape` function.
+///
+/// Text in single quotes is kept as-is, escape sequences in double quotes are
+/// parsed as in [`Dialect::Snailquote`], and the quotes themselves are
+/// removed; a quote left open runs to the end of the string. Text outside
+/// quotes is kept as-is, including backslashes. Unlike `snailquote`, strings
+/// without quotes are returned borrowed, and errors are reported as [`Error`]
+/// values without the index of the bad escape sequence.
+///
+/// ```
+/// # use unescape_zero_copy::unescape_snailquote;
+/// assert_eq!(unescape_snailquote(r#"echo "a\tb" 'c\d'"#).unwrap(), "echo a\tb c\\d");
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn unescape_snailquote(s: &str) -> Result<Cow<'_, str>, Error> {
+    const SNAILQUOTE: Options = Options {
+        dialect: Dialect::Snailquote,
+        ..Options::new()
+    };
+    let mut out = Cow::Borrowed("");
+    let mut rest = s;
+    while let Some(idx) = rest.find(['\'', '"']) {
+        out += &rest[..idx];
+        let quoted = &rest[idx + 1..];
+        if rest.as_bytes()[idx] == b'\'' {
+            let (inner, after) = quoted.split_once('\'').unwrap_or((quoted, ""));
+            out += inner;
+            rest = after;
+        } else {
+            // the character after a backslash never closes the quote
+            let mut bytes = quoted.bytes().enumerate();
+            let mut end = quoted.len();
+            while let Some((idx, byte)) = bytes.next() {
+                match byte {
+                    b'\\' => drop(bytes.next()),
+                    b'"' => {
+                        end = idx;
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+            out.to_mut().push_str(&SNAILQUOTE.unescape(&quoted[..end])?);
+            rest = quoted.get(end + 1..).unwrap_or_default();
+        }
+    }
+    out += rest;
+    Ok(out)
+}
+
+/// Parses the one escape sequence of the dialect at a byte offset of the
+/// string, without parsing the rest of the string, e.g. for showing what an
+/// escape sequence under the cursor of an editor means.
+///
+/// Returns the unescaped character (`None` for an escape sequence producing
+/// none, like the line continuations of [`Dialect::Rust`]) and the length in
+/// bytes of the escape sequence, including the backslash. Returns `None` if
+/// there's no backslash at `byte_offset`, including if it's past the end of
+/// the string or not on a character boundary.
+///
+/// ```
+/// # use unescape_zero_copy::{validate_escape_at, Dialect, Error};
+/// let s = r"tab\there \x41";
+/// assert_eq!(validate_escape_at(s, 3, Dialect::C), Some(Ok((Some('\t'), 2))));
+/// assert_eq!(validate_escape_at(s, 10, Dialect::C), Some(Ok((Some('A'), 4))));
+/// assert_eq!(
+///     validate_escape_at(s, 10, Dialect::Json),
+///     Some(Err(Error::UnknownSequence('x')))
+/// );
+/// assert_eq!(validate_escape_at(s, 0, Dialect::C), None);
+/// ```
+pub fn validate_escape_at(
+    s: &str,
+    byte_offset: usize,
+    dialect: Dialect,
+) -> Option<Result<(Option<char>, usize), Error>> {
+    let sequence = s.get(byte_offset..)?.strip_prefix('\\')?;
+    let options = Options {
+        dialect,
+        ..Options::new()
+    };
+    let parsed = options.escape_sequence(sequence);
+    Some(parsed.map(|(ch, rem)| (ch, 1 + sequence.len() - rem.len())))
+}
+
+/// Parses a variable-length code point in hex, reporting numbers too large
+/// for a `u32` as [`Error::CodePointOutOfRange`].
+#[inline]
+fn parse_hex(digits: &str) -> Result<u32, Error> {
+    u32::from_str_radix(digits, 16).map_err(|err| match err.kind() {
+        IntErrorKind::PosOverflow => Error::CodePointOutOfRange,
+        _ => Error::from(err),
+    })
+}
+
+/// Parses exactly `chars` hex digits at the start of the string, returning
+/// [`Error::IncompleteUnicode`] if the string ends first, or
+/// [`Error::InvalidHexDigit`] if another character (such as the backslash of
+/// the next escape sequence) interrupts them.
+///
+/// This can be evaluated in `const` contexts.
+pub(crate) const fn hex_digits(s: &str, chars: usize) -> Result<u32, Error> {
+    assert!(chars <= 8, "too many digits for a `u32`");
+    let bytes = s.as_bytes();
+    let mut num = 0;
+    let mut idx = 0;
+    while idx < chars {
+        if idx == bytes.len() {
+            return Err(Error::IncompleteUnicode);
+        }
+        let digit = match (bytes[idx] as char).to_digit(16) {
+            Some(digit) => digit,
+            // the digits so far are ASCII, so `idx` is a character boundary
+            None => return Err(Error::InvalidHexDigit(decode_char(bytes, idx))),
+        };
+        num = num << 4 | digit;
+        idx += 1;
+    }
+    Ok(num)
+}
+
+/// The error for a variable-width hex escape sequence with no digits at the
+/// start of the string.
+fn missing_digits(s: &str) -> Error {
+    s.chars()
+        .next()
+        .map_or(Error::IncompleteUnicode, Error::InvalidHexDigit)
+}
+
+/// Parses exactly `chars` hex digits, as [`hex_digits`] does, returning the
+/// number and the rest of the string.
+#[inline]
+pub(crate) fn hex_prefix(s: &str, chars: usize) -> Result<(u32, &str), Error> {
+    let num = hex_digits(s, chars)?;
+    // the digits are all ASCII, so `chars` is a character boundary
+    Ok((num, &s[chars..]))
+}
+
+#[inline]
+fn is_high_surrogate(num: u32) -> bool {
+    (0xD800..0xDC00).contains(&num)
+}
+
+/// Combines a high surrogate with the following code point if it's a low
+/// surrogate.
+#[inline]
+fn combine_surrogates(high: u32, (low, rem): (u32, &str)) -> Option<(char, &str)> {
+    join_surrogates(high, low).ok().map(|ch| (ch, rem))
+}
+
+impl fmt::Debug for Options<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Options");
+        s.field("dialect", &self.dialect)
+            .field("trailing_backslash", &self.trailing_backslash)
+            .field("trim_brace_whitespace", &self.trim_brace_whitespace)
+            .field("trim_line_continuation", &self.trim_line_continuation)
+            .field("unicode_plus_form", &self.unicode_plus_form)
+            .field("allow_uppercase_x", &self.allow_uppercase_x)
+            .field("allow_braced_x", &self.allow_braced_x)
+            .field("strip_bom", &self.strip_bom)
+            .field("reject_interior_bom", &self.reject_interior_bom)
+            .field("combine_braced_surrogates", &self.combine_braced_surrogates)
+            .field("reject_nul", &self.reject_nul)
+            .field("reject_noncharacters", &self.reject_noncharacters)
+            .field("accept_u_plus_prefix", &self.accept_u_plus_prefix)
+            .field("max_input_len", &self.max_input_len)
+            .field("max_escapes", &self.max_escapes)
+            .field("max_consecutive_escapes", &self.max_consecutive_escapes)
+            .field("custom_escapes", &self.custom_escapes)
+            .field("preserve_unknown", &self.preserve_unknown)
+            .field("retain_backslash", &self.retain_backslash)
+            .field("only_escape", &self.only_escape)
+            .field(
+                "invalid_unicode_replacement",
+                &self.invalid_unicode_replacement,
+            )
+            // closures aren't `Debug`, so only show whether one is set
+            .field(
+                "on_invalid_unicode",
+                &self.on_invalid_unicode.map(|_| "Fn(u32)"),
+            )
+            .field("unicode_wide", &self.unicode_wide)
+            .field("comment_char", &self.comment_char);
+        #[cfg(feature = "unicode-properties")]
+        s.field("reject_combining", &self.reject_combining);
+        s.finish()
+    }
+}
+
+impl Default for Options<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::Unescape;
+
+    fn with_policy(trailing_backslash: TrailingPolicy) -> Options<'static> {
+        Options {
+            trailing_backslash,
+            ..Options::new()
+        }
+    }
+
+    #[test]
+    fn trailing_backslash_errors_by_default() {
+        let options = with_policy(TrailingPolicy::Error);
+        assert_eq!(options.unescape("\\"), Err(Error::IncompleteSequence));
+        assert_eq!(options.unescape("abc\\"), Err(Error::IncompleteSequence));
+        assert_eq!(
+            Options::new().unescape("abc\\"),
+            Err(Error::IncompleteSequence)
+        );
+    }
+
+    #[test]
+    fn trailing_backslash_as_literal() {
+        let options = with_policy(TrailingPolicy::Literal);
+        assert_eq!(options.unescape("\\").unwrap(), "\\");
+        assert_eq!(options.unescape("abc\\").unwrap(), "abc\\");
+        assert_eq!(options.unescape(r"a\\b\").unwrap(), "a\\b\\");
+    }
+
+    #[test]
+    fn trailing_backslash_ignored() {
+        let options = with_policy(TrailingPolicy::Ignore);
+        assert_eq!(options.unescape("\\").unwrap(), "");
+        assert_eq!(options.unescape("abc\\").unwrap(), "abc");
+        assert_eq!(options.unescape(r"a\tb\").unwrap(), "a\tb");
+    }
+
+    #[test]
+    fn trims_brace_whitespace() {
+        let options = Options {
+            trim_brace_whitespace: true,
+            ..Options::new()
+        };
+        assert_eq!(options.unescape(r"\u{ 61 }").unwrap(), "a");
+        assert_eq!(options.unescape("\\u{\t1F600 }").unwrap(), "\u{1F600}");
+        assert_eq!(options.unescape(r"\u{61}").unwrap(), "a");
+        assert!(matches!(
+            Options::new().unescape(r"\u{ 61 }"),
+            Err(Error::ParseIntError(_))
+        ));
+        assert!(matches!(
+            options.unescape(r"\u{ 6 1 }"),
+            Err(Error::ParseIntError(_))
+        ));
+    }
+
+    #[test]
+    fn toml_escapes() {
+        let toml = Options {
+            dialect: Dialect::Toml,
+            ..Options::new()
+        };
+        assert_eq!(
+            toml.unescape(r#"\b\t\n\f\r\"\\\u00e9\U0001F600"#).unwrap(),
+            "\x08\t\n\x0C\r\"\\é\u{1F600}"
+        );
+        assert_eq!(toml.unescape(r"\x41"), Err(Error::UnknownSequence('x')));
+        assert_eq!(toml.unescape(r"\101"), Err(Error::UnknownSequence('1')));
+        assert_eq!(toml.unescape(r"\/"), Err(Error::UnknownSequence('/')));
+        assert_eq!(toml.unescape(r"\u{41}"), Err(Error::InvalidHexDigit('{')));
+        assert_eq!(toml.unescape("a\\\nb"), Err(Error::UnknownSequence('\n')));
+    }
+
+    #[test]
+    fn toml_line_ending_backslash() {
+        let toml = Options {
+            dialect: Dialect::Toml,
+            trim_line_continuation: true,
+            ..Options::new()
+        };
+        assert_eq!(
+            toml.unescape("The quick \\\n\n   brown \\  \r\n\tfox.")
+                .unwrap(),
+            "The quick brown fox."
+        );
+        assert_eq!(toml.unescape("a\\ b"), Err(Error::UnknownSequence(' ')));
+    }
+
+    #[test]
+    fn unicode_plus_form() {
+        let options = Options {
+            unicode_plus_form: true,
+            ..Options::new()
+        };
+        assert_eq!(options.unescape(r"\u+1F600!").unwrap(), "\u{1F600}!");
+        assert_eq!(options.unescape(r"\u+41").unwrap(), "A");
+        assert_eq!(options.unescape(r"\u0041\u{41}").unwrap(), "AA");
+        assert_eq!(options.unescape(r"\u+"), Err(Error::IncompleteUnicode));
+        assert_eq!(options.unescape(r"\u+x"), Err(Error::InvalidHexDigit('x')));
+        assert_eq!(
+            options.unescape(r"\u+123456789"),
+            Err(Error::CodePointOutOfRange)
+        );
+        let huge = format!(r"\u+{}", "0".repeat(1 << 20));
+        assert_eq!(options.unescape(&huge), Err(Error::CodePointOutOfRange));
+        assert_eq!(
+            Options::new().unescape(r"\u+41"),
+            Err(Error::InvalidHexDigit('+'))
+        );
+    }
+
+    #[test]
+    fn u_plus_prefix_in_braces() {
+        let options = Options {
+            accept_u_plus_prefix: true,
+            ..Options::new()
+        };
+        assert_eq!(
+            options.unescape(r"\u{U+1F600}\u{u+41}\u{42}").unwrap(),
+            "\u{1F600}AB"
+        );
+        let trimmed = Options {
+            trim_brace_whitespace: true,
+            ..options
+        };
+        assert_eq!(trimmed.unescape(r"\u{ U+41 }").unwrap(), "A");
+        assert!(matches!(
+            options.unescape(r"\u{U+}"),
+            Err(Error::ParseIntError(_))
+        ));
+        assert!(matches!(
+            Options::new().unescape(r"\u{U+41}"),
+            Err(Error::ParseIntError(_))
+        ));
+    }
+
+    #[test]
+    fn uppercase_x() {
+        let options = Options {
+            allow_uppercase_x: true,
+            ..Options::new()
+        };
+        assert_eq!(options.unescape(r"\X41\x42").unwrap(), "AB");
+        assert_eq!(options.unescape(r"\X4"), Err(Error::IncompleteUnicode));
+        assert_eq!(
+            Options::new().unescape(r"\X41"),
+            Err(Error::UnknownSequence('X'))
+        );
+    }
+
+    #[test]
+    fn decodes_hex_digits_in_const() {
+        const NUM: Result<u32, Error> = hex_digits("00e9x", 4);
+        assert_eq!(NUM, Ok(0xE9));
+        assert_eq!(hex_digits("0001F600", 8), Ok(0x1F600));
+        assert_eq!(hex_digits("e9", 4), Err(Error::IncompleteUnicode));
+        assert_eq!(hex_digits("e\\9", 2), Err(Error::InvalidHexDigit('\\')));
+        assert_eq!(hex_digits("e9é", 4), Err(Error::InvalidHexDigit('é')));
+        assert_eq!(hex_digits("e😀", 2), Err(Error::InvalidHexDigit('😀')));
+        assert_eq!(hex_prefix("41rest", 2), Ok((0x41, "rest")));
+    }
+
+    #[test]
+    fn braced_hex() {
+        let options = Options {
+            allow_braced_x: true,
+            ..Options::new()
+        };
+        assert_eq!(
+            options.unescape(r"\x{41}1\x{1F600}").unwrap(),
+            "A1\u{1F600}"
+        );
+        assert_eq!(options.unescape(r"\x{41"), Err(Error::IncompleteUnicode));
+        assert_eq!(
+            options.unescape(r"\x{D800}"),
+            Err(Error::InvalidUnicode(0xD800))
+        );
+        assert_eq!(
+            Options::new().unescape(r"\x{41}"),
+            Err(Error::InvalidHexDigit('{'))
+        );
+        let rust = Options {
+            dialect: Dialect::Rust,
+            ..options
+        };
+        assert_eq!(rust.unescape(r"\x{41}"), Err(Error::InvalidHexDigit('{')));
+    }
+
+    #[test]
+    fn rejects_interior_bom() {
+        let options = Options {
+            reject_interior_bom: true,
+            ..Options::new()
+        };
+        for s in ["a\u{FEFF}", "\u{FEFF}\u{FEFF}", r"a\u{feff}", r"\n\uFEFF"] {
+            assert_eq!(
+                options.unescape(s),
+                Err(Error::DisallowedChar('\u{FEFF}')),
+                "{s:?}"
+            );
+        }
+        assert_eq!(options.unescape("\u{FEFF}a").unwrap(), "\u{FEFF}a");
+        assert_eq!(options.unescape(r"\u{FEFF}\n").unwrap(), "\u{FEFF}\n");
+        let strip = Options {
+            strip_bom: true,
+            ..options
+        };
+        assert_eq!(strip.unescape("\u{FEFF}a").unwrap(), "a");
+        // only the original start of the string may hold a BOM
+        for s in ["\u{FEFF}\u{FEFF}a", "\u{FEFF}\\u{FEFF}"] {
+            assert_eq!(
+                strip.unescape(s),
+                Err(Error::DisallowedChar('\u{FEFF}')),
+                "{s:?}"
+            );
+            assert_eq!(
+                strip.unescaper(s).and_then(|mut u| u.as_cow()),
+                Err(Error::DisallowedChar('\u{FEFF}')),
+                "{s:?}"
+            );
+        }
+        assert_eq!(strip.unescape("\\u{FEFF}a").unwrap(), "\u{FEFF}a");
+        assert_eq!(Options::new().unescape(r"a\u{FEFF}").unwrap(), "a\u{FEFF}");
+    }
+
+    #[test]
+    fn strips_bom() {
+        let options = Options {
+            strip_bom: true,
+            ..Options::new()
+        };
+        assert!(matches!(
+            options.unescape("\u{FEFF}abc").unwrap(),
+            Cow::Borrowed("abc")
+        ));
+        assert_eq!(options.unescape("\u{FEFF}\\n").unwrap(), "\n");
+        assert_eq!(options.unescape("a\u{FEFF}").unwrap(), "a\u{FEFF}");
+        assert_eq!(options.unescape(r"\u{FEFF}a").unwrap(), "\u{FEFF}a");
+        assert_eq!(
+            Options::new().unescape("\u{FEFF}abc").unwrap(),
+            "\u{FEFF}abc"
+        );
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        let braced = Options {
+            combine_braced_surrogates: true,
+            ..Options::new()
+        };
+        assert_eq!(
+            Options::new().unescape(r"\uD83D\uDE00").unwrap(),
+            "\u{1F600}"
+        );
+        assert_eq!(
+            braced.unescape(r"\u{D83D}\u{DE00}\uD83D\uDE00").unwrap(),
+            "\u{1F600}\u{1F600}"
+        );
+        assert_eq!(
+            Options::new().unescape(r"\u{D83D}\u{DE00}"),
+            Err(Error::InvalidUnicode(0xD83D))
+        );
+        assert_eq!(
+            braced.unescape(r"\u{D83D}x"),
+            Err(Error::InvalidUnicode(0xD83D))
+        );
+        assert_eq!(
+            braced.unescape(r"\u{D83D}\uDE00"),
+            Err(Error::InvalidUnicode(0xD83D))
+        );
+        assert_eq!(
+            Options::new().unescape(r"\uDE00\uD83D"),
+            Err(Error::InvalidUnicode(0xDE00))
+        );
+        assert_eq!(
+            Options::new().unescape(r"\uD83D\u0041"),
+            Err(Error::InvalidUnicode(0xD83D))
+        );
+        let toml = Options {
+            dialect: Dialect::Toml,
+            ..Options::new()
+        };
+        assert_eq!(
+            toml.unescape(r"\uD83D\uDE00"),
+            Err(Error::InvalidUnicode(0xD83D))
+        );
+    }
+
+    #[test]
+    fn rejects_nul() {
+        let options = Options {
+            reject_nul: true,
+            ..Options::new()
+        };
+        for s in [r"a\0", r"\x00", r"\u{0}", r"\u0000", r"\000", "a\0b"] {
+            assert_eq!(options.unescape(s), Err(Error::NulByte), "{s:?}");
+        }
+        assert_eq!(options.unescape(r"\01").unwrap(), "\x01");
+        assert_eq!(Options::new().unescape(r"\0").unwrap(), "\0");
+        assert_eq!(Options::new().unescape("\0").unwrap(), "\0");
+    }
+
+    #[test]
+    fn rejects_noncharacters() {
+        let options = Options {
+            reject_noncharacters: true,
+            ..Options::new()
+        };
+        for (s, num) in [
+            (r"\uFFFE", 0xFFFE),
+            (r"a\u{FFFF}", 0xFFFF),
+            (r"\u{FDD0}", 0xFDD0),
+            (r"\u{fdef}", 0xFDEF),
+            (r"\U0001FFFE", 0x1FFFE),
+            (r"\u{10FFFF}", 0x10FFFF),
+        ] {
+            assert_eq!(options.unescape(s), Err(Error::Noncharacter(num)), "{s:?}");
+        }
+        for s in [r"\u{FDCF}", r"\u{FDF0}", r"\u{FFFD}", r"\u{1FFFD}"] {
+            assert!(options.unescape(s).is_ok(), "{s:?}");
+        }
+        assert_eq!(options.unescape("\u{FFFE}").unwrap(), "\u{FFFE}");
+        assert_eq!(Options::new().unescape(r"\u{FFFE}").unwrap(), "\u{FFFE}");
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-properties")]
+    fn rejects_combining() {
+        let options = Options {
+            reject_combining: true,
+            ..Options::new()
+        };
+        assert_eq!(
+            options.unescape(r"e\u{301}"),
+            Err(Error::DisallowedChar('\u{301}'))
+        );
+        assert_eq!(
+            options.unescape(r"a\u{200d}b"),
+            Err(Error::DisallowedChar('\u{200D}'))
+        );
+        assert_eq!(
+            options.unescape(r"\u{feff}"),
+            Err(Error::DisallowedChar('\u{FEFF}'))
+        );
+        assert_eq!(options.unescape("e\u{301}").unwrap(), "e\u{301}");
+        assert_eq!(options.unescape(r"\u{e9}\n").unwrap(), "\u{e9}\n");
+        assert_eq!(Options::new().unescape(r"e\u{301}").unwrap(), "e\u{301}");
+    }
+
+    #[test]
+    fn surrogate_halves_as_fragments() {
+        let halves = |options: Options, s| {
+            let mut unescaper = Unescape::new(|s| options.escape_fragment(s), s);
+            core::iter::from_fn(|| unescaper.next_fragment()).collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(
+            halves(Options::new(), r"\uD83D\uDE00\u{DE00}a\n"),
+            Ok(vec![
+                StringFragment::HighSurrogate(0xD83D),
+                StringFragment::LowSurrogate(0xDE00),
+                StringFragment::LowSurrogate(0xDE00),
+                StringFragment::Raw("a"),
+                StringFragment::Escaped('\n'),
+            ])
+        );
+        let rust = Options {
+            dialect: Dialect::Rust,
+            ..Options::new()
+        };
+        assert_eq!(halves(rust, r"\uD83D"), Err(Error::UnknownSequence('u')));
+        let options = Options::new();
+        let mut unescaper = Unescape::new(|s| options.escape_fragment(s), r"a\uD83D");
+        assert_eq!(unescaper.as_cow().unwrap(), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn minimal_escapes() {
+        let minimal = Options {
+            dialect: Dialect::Minimal,
+            ..Options::new()
+        };
+        assert_eq!(minimal.unescape(r"\n\x41\q\\\é").unwrap(), r"nx41q\é");
+        assert_eq!(Options::new().unescape(r"\n\x41\\").unwrap(), "\nA\\");
+        assert_eq!(
+            Options::new().unescape(r"\q"),
+            Err(Error::UnknownSequence('q'))
+        );
+        assert_eq!(minimal.unescape("a\\\n  b").unwrap(), "a\n  b");
+        let continued = Options {
+            trim_line_continuation: true,
+            ..minimal
+        };
+        assert_eq!(continued.unescape("a\\\n  b").unwrap(), "ab");
+        assert_eq!(minimal.unescape("a\\"), Err(Error::IncompleteSequence));
+    }
+
+    #[test]
+    fn json_escapes() {
+        let json = Options {
+            dialect: Dialect::Json,
+            ..Options::new()
+        };
+        assert_eq!(
+            json.unescape(r#"\"\\\/\b\f\n\r\t\u00e9\uD83D\uDE00"#)
+                .unwrap(),
+            "\"\\/\x08\x0C\n\r\té\u{1F600}"
+        );
+        assert_eq!(
+            json.unescape(r"\U00000041"),
+            Err(Error::UnknownSequence('U'))
+        );
+        assert_eq!(json.unescape(r"\x41"), Err(Error::UnknownSequence('x')));
+        assert_eq!(json.unescape(r"\'"), Err(Error::UnknownSequence('\'')));
+        assert_eq!(json.unescape(r"\0"), Err(Error::UnknownSequence('0')));
+        assert_eq!(json.unescape(r"\u{41}"), Err(Error::InvalidHexDigit('{')));
+    }
+
+    #[test]
+    fn snailquote_escapes() {
+        let snailquote = Options {
+            dialect: Dialect::Snailquote,
+            ..Options::new()
+        };
+        assert_eq!(
+            snailquote
+                .unescape(r#"\a\b\v\f\n\r\t\e\E\\\'\"\$\`\ \u{e9}"#)
+                .unwrap(),
+            "\x07\x08\x0B\x0C\n\r\t\x1B\x1B\\'\"$` é"
+        );
+        assert_eq!(
+            snailquote.unescape(r"\x41"),
+            Err(Error::UnknownSequence('x'))
+        );
+        assert_eq!(snailquote.unescape(r"\0"), Err(Error::UnknownSequence('0')));
+        assert_eq!(
+            snailquote.unescape(r"\u0041"),
+            Err(Error::IncompleteUnicode)
+        );
+        assert_eq!(
+            snailquote.unescape("\\\n"),
+            Err(Error::UnknownSequence('\n'))
+        );
+    }
+
+    #[test]
+    fn snailquote_quoting() {
+        assert!(matches!(
+            unescape_snailquote(r"plain\n").unwrap(),
+            Cow::Borrowed(r"plain\n")
+        ));
+        assert_eq!(unescape_snailquote(r#"a"b\"c"d"#).unwrap(), "ab\"cd");
+        assert_eq!(unescape_snailquote(r#"'\n"'"\n'""#).unwrap(), "\\n\"\n'");
+        assert_eq!(
+            unescape_snailquote(r#""\u{1F600}"''"#).unwrap(),
+            "\u{1F600}"
+        );
+        assert_eq!(unescape_snailquote(r#""open\t"#).unwrap(), "open\t");
+        assert_eq!(unescape_snailquote("'open").unwrap(), "open");
+        assert_eq!(
+            unescape_snailquote(r#""\q""#),
+            Err(Error::UnknownSequence('q'))
+        );
+        assert_eq!(
+            unescape_snailquote(r#""end\"#),
+            Err(Error::IncompleteSequence)
+        );
+    }
+
+    #[test]
+    fn rust_escapes() {
+        let rust = Options {
+            dialect: Dialect::Rust,
+            ..Options::new()
+        };
+        assert_eq!(
+            rust.unescape(r#"\n\r\t\\\0\'\"\x41\u{1F600}"#).unwrap(),
+            "\n\r\t\\\0'\"A\u{1F600}"
+        );
+        assert_eq!(rust.unescape("a\\\n    b").unwrap(), "ab");
+        assert_eq!(
+            rust.unescape(r"\U00000041"),
+            Err(Error::UnknownSequence('U'))
+        );
+        assert_eq!(rust.unescape(r"\u0041"), Err(Error::UnknownSequence('u')));
+        assert_eq!(rust.unescape(r"\x80"), Err(Error::InvalidUnicode(0x80)));
+        assert_eq!(rust.unescape(r"\a"), Err(Error::UnknownSequence('a')));
+        assert_eq!(rust.unescape(r"\1"), Err(Error::UnknownSequence('1')));
+    }
+
+    #[test]
+    fn postgres_escapes() {
+        let postgres = Options {
+            dialect: Dialect::Postgres,
+            ..Options::new()
+        };
+        assert_eq!(
+            postgres
+                .unescape(r"\b\f\n\r\t\101\7\x41\x9\u00e9\U0001F600")
+                .unwrap(),
+            "\x08\x0C\n\r\tA\x07A\x09é\u{1F600}"
+        );
+        assert_eq!(postgres.unescape(r"\1018\x414").unwrap(), "A8A4");
+        assert_eq!(postgres.unescape(r"\\\'\q\xg\a").unwrap(), "\\'qxga");
+        assert_eq!(postgres.unescape(r"\uD83D\uDE00").unwrap(), "\u{1F600}");
+        assert_eq!(
+            postgres.unescape(r"\u{41}"),
+            Err(Error::InvalidHexDigit('{'))
+        );
+        assert_eq!(postgres.unescape(r"\u12"), Err(Error::IncompleteUnicode));
+    }
+
+    #[test]
+    fn java_escapes() {
+        let java = Options {
+            dialect: Dialect::Java,
+            ..Options::new()
+        };
+        assert_eq!(
+            java.unescape(r#"\b\t\n\f\r\"\'\\"#).unwrap(),
+            "\x08\t\n\x0C\r\"'\\"
+        );
+        // octal escapes from the Java Language Specification
+        assert_eq!(java.unescape(r"\0\12\101\377").unwrap(), "\0\nA\u{FF}");
+        assert_eq!(java.unescape(r"\400\777\08").unwrap(), " 0?7\x008");
+        assert_eq!(java.unescape(r"A\uuu0042").unwrap(), "AB");
+        assert_eq!(java.unescape(r"\uD83D\uDE00").unwrap(), "\u{1F600}");
+        assert_eq!(java.unescape(r"\x41"), Err(Error::UnknownSequence('x')));
+        assert_eq!(
+            java.unescape(r"\U0001F600"),
+            Err(Error::UnknownSequence('U'))
+        );
+        assert_eq!(java.unescape(r"\a"), Err(Error::UnknownSequence('a')));
+        assert_eq!(java.unescape(r"\8"), Err(Error::UnknownSequence('8')));
+        assert_eq!(java.unescape(r"\u{41}"), Err(Error::InvalidHexDigit('{')));
+        assert_eq!(java.unescape(r"\u12"), Err(Error::IncompleteUnicode));
+    }
+
+    #[test]
+    fn validates_one_escape() {
+        let validate = |s, offset, dialect| validate_escape_at(s, offset, dialect).unwrap();
+        let s = r"a\n\u{1F600}\uD83D\uDE00\101\q";
+        assert_eq!(validate(s, 1, Dialect::C), Ok((Some('\n'), 2)));
+        assert_eq!(validate(s, 3, Dialect::C), Ok((Some('\u{1F600}'), 9)));
+        assert_eq!(validate(s, 12, Dialect::C), Ok((Some('\u{1F600}'), 12)));
+        assert_eq!(validate(s, 12, Dialect::Json), Ok((Some('\u{1F600}'), 12)));
+        assert_eq!(validate(s, 24, Dialect::C), Ok((Some('A'), 4)));
+        assert_eq!(validate(s, 24, Dialect::Java), Ok((Some('A'), 4)));
+        assert_eq!(
+            validate(s, 28, Dialect::C),
+            Err(Error::UnknownSequence('q'))
+        );
+        assert_eq!(validate(s, 28, Dialect::Minimal), Ok((Some('q'), 2)));
+        assert_eq!(
+            validate(s, 3, Dialect::Json),
+            Err(Error::InvalidHexDigit('{'))
+        );
+        assert_eq!(validate("\\\n  a", 0, Dialect::Rust), Ok((None, 4)));
+        assert_eq!(validate("\\\n  a", 0, Dialect::C), Ok((Some('\n'), 2)));
+        assert_eq!(
+            validate("a\\", 1, Dialect::C),
+            Err(Error::IncompleteSequence)
+        );
+    }
+
+    #[test]
+    fn validating_needs_a_backslash() {
+        assert_eq!(validate_escape_at(r"a\n", 0, Dialect::C), None);
+        assert_eq!(validate_escape_at(r"a\n", 3, Dialect::C), None);
+        assert_eq!(validate_escape_at(r"a\n", 4, Dialect::C), None);
+        assert_eq!(validate_escape_at(r"é\n", 1, Dialect::C), None);
+    }
+
+    #[test]
+    fn postgres_doubled_quotes() {
+        let postgres = Options {
+            dialect: Dialect::Postgres,
+            ..Options::new()
+        };
+        assert_eq!(postgres.unescape("it''s").unwrap(), "it's");
+        assert_eq!(postgres.unescape(r"''\n''''").unwrap(), "'\n''");
+        assert_eq!(postgres.unescape(r"\'''").unwrap(), "''");
+        assert!(matches!(
+            postgres.unescape("no quotes").unwrap(),
+            Cow::Borrowed("no quotes")
+        ));
+        assert_eq!(Options::new().unescape("it''s").unwrap(), "it''s");
+    }
+
+    #[test]
+    fn limits_work() {
+        let limited = Options {
+            max_input_len: Some(8),
+            max_escapes: Some(2),
+            ..Options::new()
+        };
+        assert_eq!(limited.unescape(r"a\tb\n").unwrap(), "a\tb\n");
+        assert_eq!(limited.unescape("12345678").unwrap(), "12345678");
+        assert_eq!(limited.unescape("123456789"), Err(Error::LimitExceeded));
+        assert_eq!(limited.unescape(r"\t\t\t"), Err(Error::LimitExceeded));
+        let postgres = Options {
+            dialect: Dialect::Postgres,
+            ..limited
+        };
+        assert_eq!(postgres.unescape(r"\t''\t\t"), Err(Error::LimitExceeded));
+        let runs = Options {
+            max_consecutive_escapes: Some(2),
+            ..Options::new()
+        };
+        assert_eq!(runs.unescape(r"\t\ta\t\tb\t\t").unwrap(), "\t\ta\t\tb\t\t");
+        assert_eq!(runs.unescape(r"a\t\t\tb"), Err(Error::LimitExceeded));
+        assert_eq!(runs.unescape(r"\x41\u{42}\103"), Err(Error::LimitExceeded));
+        let postgres = Options {
+            dialect: Dialect::Postgres,
+            ..runs
+        };
+        assert_eq!(postgres.unescape(r"\t\t''\t\t").unwrap(), "\t\t'\t\t");
+        let huge = r"\t".repeat(1 << 16);
+        assert_eq!(Options::new().unescape(&huge).unwrap().len(), 1 << 16);
+    }
+
+    #[test]
+    fn unescaper_enforces_whole_input_options() {
+        let options = Options {
+            strip_bom: true,
+            reject_nul: true,
+            reject_interior_bom: true,
+            max_input_len: Some(12),
+            max_consecutive_escapes: Some(2),
+            ..Options::new()
+        };
+        let unescape = |s| options.unescaper(s)?.as_cow();
+        assert_eq!(unescape("\u{FEFF}a\\tb").unwrap(), "a\tb");
+        assert_eq!(unescape("a\0"), Err(Error::NulByte));
+        assert_eq!(
+            unescape("a\u{FEFF}"),
+            Err(Error::DisallowedChar('\u{FEFF}'))
+        );
+        assert_eq!(
+            unescape(r"a\u{FEFF}"),
+            Err(Error::DisallowedChar('\u{FEFF}'))
+        );
+        assert_eq!(unescape("1234567890123"), Err(Error::LimitExceeded));
+        assert_eq!(unescape(r"\t\t\t"), Err(Error::LimitExceeded));
+        for s in ["\u{FEFF}a\\tb", "a\0", r"\t\t\t", r"a\u{FEFF}"] {
+            assert_eq!(
+                unescape(s).map(Cow::into_owned),
+                options.unescape(s).map(Cow::into_owned),
+                "{s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn runtime_tables() {
+        let config = "N=\n T=\t";
+        let custom: Vec<(char, char)> = config
+            .split(' ')
+            .map(|pair| {
+                let mut chars = pair.chars();
+                (chars.next().unwrap(), chars.nth(1).unwrap())
+            })
+            .collect();
+        let retained: Vec<char> = "*[".chars().collect();
+        let options = Options {
+            custom_escapes: &custom,
+            retain_backslash: &retained,
+            ..Options::new()
+        };
+        assert_eq!(options.unescape(r"a\Nb\T\*").unwrap(), "a\nb\t\\*");
+        let only: Vec<char> = r#""\"#.chars().collect();
+        let options = Options {
+            only_escape: Some(&only),
+            ..Options::new()
+        };
+        assert_eq!(options.unescape(r#"\"\\"#).unwrap(), r#""\"#);
+        assert_eq!(options.unescape(r"\n"), Err(Error::UnknownSequence('n')));
+    }
+
+    #[test]
+    fn custom_escapes() {
+        let custom = Options {
+            custom_escapes: &[('N', '\n'), ('q', '`'), ('n', 'N')],
+            ..Options::new()
+        };
+        assert_eq!(custom.unescape(r"a\Nb\qc\n\t").unwrap(), "a\nb`cN\t");
+        assert_eq!(custom.unescape(r"\z"), Err(Error::UnknownSequence('z')));
+        let nul = Options {
+            custom_escapes: &[('z', '\0')],
+            reject_nul: true,
+            ..Options::new()
+        };
+        assert_eq!(nul.unescape(r"\z"), Err(Error::NulByte));
+        assert_eq!(
+            Options::new().unescape(r"\N"),
+            Err(Error::UnknownSequence('N'))
+        );
+    }
+
+    #[test]
+    fn preserves_unknown_escapes() {
+        let options = Options {
+            preserve_unknown: true,
+            ..Options::new()
+        };
+        let fragments = |s| {
+            let mut unescaper = Unescape::new(|s| options.escape_fragment(s), s);
+            core::iter::from_fn(|| unescaper.next_fragment()).collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(
+            fragments(r"a\q\n\é\8"),
+            Ok(vec![
+                StringFragment::Raw("a"),
+                StringFragment::Verbatim(r"\q"),
+                StringFragment::Escaped('\n'),
+                StringFragment::Verbatim(r"\é"),
+                StringFragment::Verbatim(r"\8"),
+            ])
+        );
+        assert_eq!(fragments(r"\x4"), Err(Error::IncompleteUnicode));
+        let mut unescaper = Unescape::new(|s| options.escape_fragment(s), r"a\qb\t");
+        assert_eq!(unescaper.as_cow().unwrap(), "a\\qb\t");
+        let mut unescaper = Unescape::new(|s| Options::new().escape_fragment(s), r"\q");
+        assert_eq!(unescaper.as_cow(), Err(Error::UnknownSequence('q')));
+        // the same through the options' own unescapers
+        assert_eq!(options.unescape(r"a\qb\t\8").unwrap(), "a\\qb\t\\8");
+        let mut unescaper = options.unescaper(r"a\q").unwrap();
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("a")))
+        );
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Verbatim(r"\q")))
+        );
+        assert_eq!(options.unescape(r"\x4"), Err(Error::IncompleteUnicode));
+    }
+
+    #[test]
+    fn only_escapes_listed_characters() {
+        let options = Options {
+            only_escape: Some(&['"', '\\']),
+            ..Options::new()
+        };
+        assert_eq!(options.unescape(r#"a\"b\\"#).unwrap(), r#"a"b\"#);
+        for (s, c) in [
+            (r"\n", 'n'),
+            (r"\u0041", 'u'),
+            (r"\x41", 'x'),
+            (r"\'", '\''),
+        ] {
+            assert_eq!(options.unescape(s), Err(Error::UnknownSequence(c)), "{s:?}");
+        }
+        let preserving = Options {
+            preserve_unknown: true,
+            ..options
+        };
+        let mut unescaper = Unescape::new(|s| preserving.escape_fragment(s), r#"\n\uD800\""#);
+        assert_eq!(unescaper.as_cow().unwrap(), r#"\n\uD800""#);
+        assert_eq!(preserving.unescape(r#"\n\""#).unwrap(), r#"\n""#);
+        let custom = Options {
+            custom_escapes: &[('n', '\n')],
+            ..options
+        };
+        assert_eq!(custom.unescape(r"a\nb").unwrap(), "a\nb");
+        // with doubled quotes, for SQL-like literals
+        let postgres = Options {
+            dialect: Dialect::Postgres,
+            only_escape: Some(&['\\']),
+            ..Options::new()
+        };
+        assert_eq!(postgres.unescape(r"it''s \\").unwrap(), r"it's \");
+        assert_eq!(postgres.unescape(r"\n"), Err(Error::UnknownSequence('n')));
+    }
+
+    #[test]
+    fn supported_escapes_unescape_as_listed() {
+        for dialect in [
+            Dialect::C,
+            Dialect::Toml,
+            Dialect::Json,
+            Dialect::Rust,
+            Dialect::Minimal,
+            Dialect::Postgres,
+            Dialect::Snailquote,
+            Dialect::Java,
+        ] {
+            let options = Options {
+                dialect,
+                ..Options::new()
+            };
+            assert!(!dialect.supported_escapes().is_empty());
+            for spec in dialect.supported_escapes() {
+                assert_eq!(
+                    options.unescape(spec.example).as_deref(),
+                    Ok(spec.decoded),
+                    "{dialect:?} {spec:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn retains_backslashes() {
+        let options = Options {
+            retain_backslash: &['*', '[', 'u'],
+            ..Options::new()
+        };
+        assert_eq!(options.unescape(r"\*bold\*"), Ok(r"\*bold\*".into()));
+        assert_eq!(options.unescape(r"\[link]\n"), Ok("\\[link]\n".into()));
+        assert_eq!(options.unescape(r"\n\t\\"), Ok("\n\t\\".into()));
+        assert_eq!(options.unescape(r"\u{41}\x41"), Ok(r"\u{41}A".into()));
+        assert_eq!(options.unescape(r"\*\q"), Err(Error::UnknownSequence('q')));
+        let mut unescaper = Unescape::new(|s| options.escape_fragment(s), r"a\*\n");
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Raw("a")))
+        );
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Verbatim(r"\*")))
+        );
+        assert_eq!(
+            unescaper.next_fragment(),
+            Some(Ok(StringFragment::Escaped('\n')))
+        );
+        let options = Options {
+            comment_char: Some('#'),
+            ..options
+        };
+        assert_eq!(
+            options.unescape("\\*\\n# \\*\n\\*"),
+            Ok("\\*\n# \\*\n\\*".into())
+        );
+    }
+
+    #[test]
+    fn replaces_invalid_unicode() {
+        let options = Options {
+            invalid_unicode_replacement: Some('?'),
+            ..Options::new()
+        };
+        assert_eq!(
+            options.unescape(r"a\uD83Db\u{110000}\uDE00😀").unwrap(),
+            "a?b??\u{1F600}"
+        );
+        assert_eq!(options.unescape(r"\q"), Err(Error::UnknownSequence('q')));
+        assert_eq!(
+            options.unescape(r"\u{100000000}"),
+            Err(Error::CodePointOutOfRange)
+        );
+        let rust = Options {
+            dialect: Dialect::Rust,
+            ..options
+        };
+        assert_eq!(rust.unescape(r"\x80\x41").unwrap(), "?A");
+    }
+
+    #[test]
+    fn handles_invalid_unicode() {
+        let options = Options {
+            on_invalid_unicode: Some(&|num| match num {
+                0xD800..=0xDBFF => InvalidAction::Skip,
+                0xDC00..=0xDFFF => InvalidAction::Replace('?'),
+                _ => InvalidAction::Error,
+            }),
+            invalid_unicode_replacement: Some('!'),
+            ..Options::new()
+        };
+        assert_eq!(
+            options.unescape(r"a\uD83Db\uDE00\u{D800}\xff").unwrap(),
+            "ab?\u{FF}"
+        );
+        assert_eq!(
+            options.unescape(r"\uD83D\uDE00\uD83DA").unwrap(),
+            "\u{1F600}A"
+        );
+        assert_eq!(
+            options.unescape(r"\u{110000}"),
+            Err(Error::InvalidUnicode(0x110000))
+        );
+        assert_eq!(
+            options.unescape(r"\uD83D\q"),
+            Err(Error::UnknownSequence('q'))
+        );
+        let rust = Options {
+            dialect: Dialect::Rust,
+            on_invalid_unicode: Some(&|_| InvalidAction::Skip),
+            ..options
+        };
+        assert_eq!(rust.unescape(r"a\x80b").unwrap(), "ab");
+        let mut unescaper = Unescape::new(|s| rust.escape_sequence(s), r"\x80");
+        assert_eq!(unescaper.next(), Some(Ok(None)));
+        assert_eq!(unescaper.next(), None);
+    }
+
+    #[test]
+    fn invalid_unicode_closure_captures_state() {
+        let skipped = core::cell::Cell::new(0);
+        let count = |_| {
+            skipped.set(skipped.get() + 1);
+            InvalidAction::Skip
+        };
+        let options = Options {
+            on_invalid_unicode: Some(&count),
+            ..Options::new()
+        };
+        assert_eq!(
+            options.unescape(r"a\uD800b\u{DFFF}c\U00110000").unwrap(),
+            "abc"
+        );
+        assert_eq!(skipped.get(), 3);
+        assert!(format!("{options:?}").contains("on_invalid_unicode: Some("));
+    }
+
+    #[test]
+    fn wide_unicode_escapes() {
+        let wide = Options {
+            unicode_wide: true,
+            ..Options::new()
+        };
+        assert_eq!(
+            wide.unescape(r"\u1F600\u10FFFFé\u41!").unwrap(),
+            "\u{1F600}\u{10FFFF}é\u{41}!"
+        );
+        assert_eq!(wide.unescape(r"\u1000000").unwrap(), "\u{100000}0");
+        assert_eq!(wide.unescape(r"\uD83D\uDE00").unwrap(), "\u{1F600}");
+        assert_eq!(
+            wide.unescape(r"\u110000"),
+            Err(Error::InvalidUnicode(0x110000))
+        );
+        assert_eq!(wide.unescape(r"\ux"), Err(Error::InvalidHexDigit('x')));
+        assert_eq!(wide.unescape(r"\u"), Err(Error::IncompleteUnicode));
+        assert_eq!(Options::new().unescape(r"\u1F600").unwrap(), "\u{1F60}0");
+    }
+
+    #[test]
+    fn keeps_comments_verbatim() {
+        let options = Options {
+            comment_char: Some('#'),
+            custom_escapes: &[('#', '#')],
+            ..Options::new()
+        };
+        assert_eq!(
+            options
+                .unescape("a\\tb # c\\td \\q\ne\\t\\# f # \\n")
+                .unwrap(),
+            "a\tb # c\\td \\q\ne\t# f # \\n"
+        );
+        assert_eq!(options.unescape("a # \\q").unwrap(), "a # \\q");
+        assert!(matches!(
+            options.unescape("no comment").unwrap(),
+            Cow::Borrowed("no comment")
+        ));
+        assert_eq!(
+            options.unescape("\\q # a"),
+            Err(Error::UnknownSequence('q'))
+        );
+        let postgres = Options {
+            dialect: Dialect::Postgres,
+            ..options
+        };
+        assert_eq!(postgres.unescape("it''s # it''s").unwrap(), "it's # it''s");
+    }
+}
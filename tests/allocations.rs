@@ -0,0 +1,62 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::cell::Cell;
+use unescape_zero_copy::{decodes_to, unescape_default};
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let out = f();
+    (out, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[test]
+fn escape_heavy_input_allocates_once() {
+    let input = r"ab\n\t\u{1F600}\x41".repeat(1000);
+    let (out, count) = allocations(|| unescape_default(&input).unwrap());
+    assert!(matches!(out, Cow::Owned(_)));
+    assert_eq!(count, 1);
+
+    let input = r"\n\t\x41é\u{1F600}\\".repeat(1000);
+    let (_, count) = allocations(|| unescape_default(&input).unwrap());
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn input_without_escapes_never_allocates() {
+    let input = "no escapes here".repeat(1000);
+    let (out, count) = allocations(|| unescape_default(&input).unwrap());
+    assert!(matches!(out, Cow::Borrowed(_)));
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn decoded_comparison_never_allocates() {
+    let escaped = r"ab\n\t\u{1F600}\x41".repeat(1000);
+    let expected = "ab\n\t\u{1F600}A".repeat(1000);
+    let (out, count) = allocations(|| decodes_to(&escaped, &expected));
+    assert_eq!(out, Ok(true));
+    assert_eq!(count, 0);
+}
@@ -0,0 +1,54 @@
+use std::borrow::Cow;
+use unescape_zero_copy::{unescape, Error, Options, StringFragment, Unescape};
+
+// Uppercase letters standing for control characters, as in some mainframe
+// record formats, kept apart from the lowercase C escapes.
+const LEGACY: Options = Options {
+    custom_escapes: &[
+        ('N', '\n'),
+        ('R', '\r'),
+        ('T', '\t'),
+        ('F', '\x0C'),
+        ('E', '\x1B'),
+    ],
+    ..Options::new()
+};
+
+#[test]
+fn uppercase_control_escapes() {
+    assert_eq!(LEGACY.unescape(r"line\Nnext").unwrap(), "line\nnext");
+    assert_eq!(
+        LEGACY.unescape(r"\Ecolor\T\R\N\F").unwrap(),
+        "\x1Bcolor\t\r\n\x0C"
+    );
+    assert!(matches!(
+        LEGACY.unescape("no escapes").unwrap(),
+        Cow::Borrowed(_)
+    ));
+    assert_eq!(LEGACY.unescape(r"\Q"), Err(Error::UnknownSequence('Q')));
+}
+
+#[test]
+fn lowercase_escapes_still_work() {
+    assert_eq!(LEGACY.unescape(r"a\nb\Nc").unwrap(), "a\nb\nc");
+    assert_eq!(LEGACY.unescape(r"\t\T").unwrap(), "\t\t");
+    assert_eq!(LEGACY.unescape(r"\x41\\N").unwrap(), "A\\N");
+}
+
+#[test]
+fn uppercase_escapes_through_iterator() {
+    let mut unescaper = Unescape::new(|s| LEGACY.escape_fragment(s), r"id\Nname");
+    let fragments = std::iter::from_fn(|| unescaper.next_fragment())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        fragments,
+        [
+            StringFragment::Raw("id"),
+            StringFragment::Escaped('\n'),
+            StringFragment::Raw("name"),
+        ]
+    );
+    let via_sequence = unescape(|s| LEGACY.escape_sequence(s), r"a\Nb").unwrap();
+    assert_eq!(via_sequence, "a\nb");
+}
@@ -38,7 +38,7 @@ fn lua_escape_sequence(s: &str) -> Result<(Option<char>, &str), Error> {
             }
         }
         _ => {
-            let count = s.chars().take_while(|n| n.is_digit(10)).count().min(3);
+            let count = s.chars().take_while(|n| n.is_ascii_digit()).count().min(3);
             if count > 0 {
                 let num: u32 = s[0..count].parse()?;
                 let ch = char::from_u32(num).ok_or(Error::InvalidUnicode(num))?;